@@ -0,0 +1,42 @@
+use aoc2019::intcode::asm::assemble_with;
+use aoc2019::intcode::opcodes::OpcodeRegistry;
+use aoc2019::intcode::syscall::Syscalls;
+use itertools::Itertools;
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::process::exit;
+
+fn main() {
+    let mut args: Vec<String> = env::args().collect();
+    // accept the SYS instruction
+    let opcodes = match args.iter().position(|arg| arg == "--syscalls") {
+        Some(position) => {
+            args.remove(position);
+            Syscalls::new().opcodes()
+        }
+        None => OpcodeRegistry::new(),
+    };
+    let source = match args.len() {
+        1 => {
+            let mut source = String::new();
+            io::stdin().read_to_string(&mut source).unwrap();
+            source
+        }
+        2 => fs::read_to_string(&args[1]).unwrap_or_else(|err| {
+            eprintln!("{}: {}", args[1], err);
+            exit(1);
+        }),
+        _ => {
+            eprintln!("Usage: intcode-asm [--syscalls] [SOURCE_FILE]");
+            exit(2);
+        }
+    };
+    match assemble_with(&source, &opcodes) {
+        Ok(intcode) => println!("{}", intcode.iter().join(",")),
+        Err(err) => {
+            eprintln!("{}", err);
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use aoc2019::intcode::{self, cfg::Cfg, fail};
+use std::env;
+
+const USAGE: &str = "\
+Usage: intcode-cfg PROGRAM_FILE [--entry ADDR]... [--summary]
+
+Prints the static control-flow graph of the program in Graphviz DOT, e.g.
+    intcode-cfg data/day21.txt | dot -Tsvg > day21.svg";
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut path = None;
+    let mut entries = vec![0];
+    let mut summary = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--entry" => {
+                let address = args.next().unwrap_or_else(|| fail(USAGE));
+                entries.push(
+                    address
+                        .parse()
+                        .unwrap_or_else(|err| fail(format!("{}: {}", address, err))),
+                );
+            }
+            "--summary" => summary = true,
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+    let Some(path) = path else {
+        fail(USAGE);
+    };
+    let intcode = intcode::load(&path).unwrap_or_else(|err| fail(format!("{}: {}", path, err)));
+    let cfg = Cfg::build_with_entries(&intcode, &entries);
+    if summary {
+        println!("{}", cfg.summary());
+    } else {
+        print!("{}", cfg.to_dot(&intcode));
+    }
+    eprintln!(
+        "{} blocks, {} edges, {} computed jumps",
+        cfg.blocks.len(),
+        cfg.num_edges(),
+        cfg.computed_jumps().count()
+    );
+}
@@ -0,0 +1,358 @@
+use aoc2019::intcode::disasm::{decode, format_instruction};
+use aoc2019::intcode::{self, op_info, Program, ProgramState::*};
+use hashbrown::HashSet;
+use itertools::Itertools;
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::process::exit;
+
+const HELP: &str = "\
+Commands:
+  s, step [N]          execute N instructions (default 1)
+  c, continue [N]      run until a breakpoint, a watchpoint, input is needed,
+                       the program exits, or N instructions were executed
+  rs, rstep [N]        undo the last N instructions (default 1)
+  rc, rcontinue        undo instructions until a breakpoint, a watchpoint, or
+                       the oldest instruction recorded
+  who ADDR             show the last instruction that wrote to ADDR
+  b, break ADDR        set a breakpoint on the instruction at ADDR
+  w, watch ADDR        set a watchpoint on memory writes to ADDR
+  d, delete ADDR       delete the breakpoint and watchpoint at ADDR
+  l, list              list breakpoints and watchpoints
+  x ADDR [N]           print N memory words starting at ADDR (default 1)
+  set ADDR VALUE       write VALUE to memory at ADDR
+  dis [ADDR] [N]       disassemble N instructions from ADDR (default: next 5)
+  i, info              show the instruction pointer, relative base and state
+  in VALUE...          queue input values
+  ascii TEXT           queue TEXT followed by a newline as ASCII input
+  out                  show pending outputs without consuming them
+  recv                 consume and print pending outputs (as text if ASCII)
+  h, help              show this help
+  q, quit              exit the debugger
+An empty line repeats the previous command.";
+
+// instructions that can be undone
+const HISTORY_LIMIT: usize = 1_000_000;
+
+struct Debugger {
+    program: Program,
+    breakpoints: HashSet<usize>,
+    watchpoints: HashSet<usize>,
+    num_steps: usize,
+}
+
+impl Debugger {
+    fn disassemble_at(&self, address: usize) -> (String, usize) {
+        let window: Vec<isize> = (address..address + 4)
+            .map(|a| self.program.read(a))
+            .collect();
+        match decode(&window, 0) {
+            Some((op, params)) => (format_instruction(op, &params, None), 1 + params.len()),
+            None => (format!("DATA {}", window[0]), 1),
+        }
+    }
+
+    fn print_location(&self) {
+        let (text, _) = self.disassemble_at(self.program.index());
+        println!("{:>6}: {}", self.program.index(), text);
+    }
+
+    fn print_info(&self) {
+        println!("index:         {}", self.program.index());
+        println!("relative base: {}", self.program.relative_base());
+        println!("state:         {:?}", self.program.state());
+        println!("steps:         {}", self.num_steps);
+        if let Some(error) = self.program.fault() {
+            println!("fault:         {}", error);
+        }
+    }
+
+    // the address the next instruction will write to, if any
+    fn next_write(&self) -> Option<usize> {
+        let (op, args) = self.program.instruction().ok()?;
+        if op == 3 && self.program.inputs().next().is_none() {
+            return None;
+        }
+        op_info(op)?.result_arg.map(|result_arg| args[result_arg])
+    }
+
+    // executes one instruction, returning false if execution should stop
+    fn step(&mut self) -> bool {
+        let index = self.program.index();
+        let write = self.next_write();
+        let old_value = write.map(|address| self.program.read(address));
+        if let Err(error) = self.program.step() {
+            println!("Fault: {}", error);
+            return false;
+        }
+        match self.program.state() {
+            WaitingForInput => {
+                println!("Waiting for input at {}", index);
+                return false;
+            }
+            Exited => {
+                println!("Program exited after {} steps", self.num_steps);
+                return false;
+            }
+            _ => {}
+        }
+        self.num_steps += 1;
+        if let (Some(address), Some(old_value)) = (write, old_value) {
+            if self.watchpoints.contains(&address) {
+                let new_value = self.program.read(address);
+                println!(
+                    "Watchpoint: &{} changed from {} to {} by instruction at {}",
+                    address, old_value, new_value, index
+                );
+                return false;
+            }
+        }
+        true
+    }
+
+    // undoes one instruction, returning false if execution should stop
+    fn step_back(&mut self) -> bool {
+        let Some(step) = self
+            .program
+            .history()
+            .and_then(|history| history.steps().last())
+        else {
+            println!("No more history");
+            return false;
+        };
+        let watched = step
+            .writes
+            .iter()
+            .find(|(address, _)| self.watchpoints.contains(address))
+            .map(|(address, value)| (*address, *value, step.index));
+        self.program.step_back();
+        self.num_steps = self.num_steps.saturating_sub(1);
+        if let Some((address, old_value, index)) = watched {
+            println!(
+                "Watchpoint: &{} changed back to {} before instruction at {}",
+                address, old_value, index
+            );
+            return false;
+        }
+        true
+    }
+
+    fn run_back(&mut self) {
+        while self.step_back() {
+            if self.breakpoints.contains(&self.program.index()) {
+                println!("Breakpoint at {}", self.program.index());
+                break;
+            }
+        }
+        self.print_location();
+    }
+
+    fn run(&mut self, max_steps: Option<usize>) {
+        let mut steps = 0;
+        while max_steps.is_none_or(|max_steps| steps < max_steps) {
+            if !self.step() {
+                break;
+            }
+            steps += 1;
+            if self.breakpoints.contains(&self.program.index()) {
+                println!("Breakpoint at {}", self.program.index());
+                break;
+            }
+        }
+        self.print_location();
+    }
+
+    fn execute(&mut self, command: &str, args: &[&str]) -> Result<bool, String> {
+        let parse = |index: usize| -> Result<Option<isize>, String> {
+            match args.get(index) {
+                Some(arg) => arg
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| format!("invalid number '{}'", arg)),
+                None => Ok(None),
+            }
+        };
+        let parse_address = |index: usize| -> Result<Option<usize>, String> {
+            match parse(index)? {
+                Some(address) if address < 0 => Err(format!("invalid address {}", address)),
+                address => Ok(address.map(|address| address as usize)),
+            }
+        };
+        let required = |value: Option<usize>| value.ok_or("missing address".to_string());
+        match command {
+            "s" | "step" => {
+                let num_steps = parse_address(0)?.unwrap_or(1);
+                for _ in 0..num_steps {
+                    if !self.step() {
+                        break;
+                    }
+                }
+                self.print_location();
+            }
+            "c" | "continue" => self.run(parse_address(0)?),
+            "rs" | "rstep" => {
+                let num_steps = parse_address(0)?.unwrap_or(1);
+                for _ in 0..num_steps {
+                    if !self.step_back() {
+                        break;
+                    }
+                }
+                self.print_location();
+            }
+            "rc" | "rcontinue" => self.run_back(),
+            "who" => {
+                let address = required(parse_address(0)?)?;
+                let history = self.program.history().unwrap();
+                match history.last_write(address) {
+                    Some(step) => {
+                        let (text, _) = self.disassemble_at(step.index);
+                        let old_value = step
+                            .writes
+                            .iter()
+                            .rev()
+                            .find(|(written, _)| *written == address)
+                            .unwrap()
+                            .1;
+                        println!(
+                            "&{} was last written at step {} by {:>6}: {} (was {})",
+                            address, step.number, step.index, text, old_value
+                        );
+                    }
+                    None => println!(
+                        "&{} was not written in the last {} steps",
+                        address,
+                        history.len()
+                    ),
+                }
+            }
+            "b" | "break" => {
+                self.breakpoints.insert(required(parse_address(0)?)?);
+            }
+            "w" | "watch" => {
+                self.watchpoints.insert(required(parse_address(0)?)?);
+            }
+            "d" | "delete" => {
+                let address = required(parse_address(0)?)?;
+                self.breakpoints.remove(&address);
+                self.watchpoints.remove(&address);
+            }
+            "l" | "list" => {
+                println!(
+                    "breakpoints: {}",
+                    self.breakpoints.iter().sorted().join(", ")
+                );
+                println!(
+                    "watchpoints: {}",
+                    self.watchpoints.iter().sorted().join(", ")
+                );
+            }
+            "x" => {
+                let address = required(parse_address(0)?)?;
+                let count = parse_address(1)?.unwrap_or(1);
+                let end = address
+                    .checked_add(count)
+                    .ok_or("address range out of bounds")?;
+                for address in address..end {
+                    println!("{:>6}: {}", address, self.program.read(address));
+                }
+            }
+            "set" => {
+                let address = required(parse_address(0)?)?;
+                let value = parse(1)?.ok_or("missing value")?;
+                self.program.write(address, value);
+            }
+            "dis" => {
+                let mut address = parse_address(0)?.unwrap_or(self.program.index());
+                for _ in 0..parse_address(1)?.unwrap_or(5) {
+                    let (text, length) = self.disassemble_at(address);
+                    let marker = if address == self.program.index() {
+                        "=>"
+                    } else {
+                        "  "
+                    };
+                    println!("{}{:>6}: {}", marker, address, text);
+                    address = address.checked_add(length).ok_or("address out of bounds")?;
+                }
+            }
+            "i" | "info" => self.print_info(),
+            "in" => {
+                for index in 0..args.len() {
+                    self.program.send(parse(index)?.unwrap());
+                }
+            }
+            "ascii" => {
+                for b in args.join(" ").bytes().chain([b'\n']) {
+                    self.program.send(b as isize);
+                }
+            }
+            "out" => println!("{}", self.program.outputs().join(", ")),
+            "recv" => {
+                let mut outputs = vec![];
+                while let Some(output) = self.program.receive() {
+                    outputs.push(output);
+                }
+                if outputs.iter().all(|output| (0..128).contains(output)) {
+                    print!(
+                        "{}",
+                        outputs.iter().map(|output| *output as u8 as char).join("")
+                    );
+                } else {
+                    println!("{}", outputs.iter().join(", "));
+                }
+            }
+            "h" | "help" => println!("{}", HELP),
+            "q" | "quit" => return Ok(false),
+            _ => {
+                return Err(format!(
+                    "unknown command '{}', type 'help' for help",
+                    command
+                ))
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 2 {
+        eprintln!("Usage: intcode-dbg PROGRAM_FILE");
+        exit(2);
+    }
+    let intcode = intcode::load(&args[1]).unwrap_or_else(|err| {
+        eprintln!("{}: {}", args[1], err);
+        exit(1);
+    });
+    let mut debugger = Debugger {
+        program: Program::new(&intcode),
+        breakpoints: HashSet::new(),
+        watchpoints: HashSet::new(),
+        num_steps: 0,
+    };
+    debugger.program.start_recording(HISTORY_LIMIT);
+    debugger.print_location();
+    let stdin = io::stdin();
+    let mut previous_command = String::new();
+    loop {
+        print!("(dbg) ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        let line = match line.trim() {
+            "" => previous_command.clone(),
+            line => line.to_string(),
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((command, command_args)) = words.split_first() else {
+            continue;
+        };
+        match debugger.execute(command, command_args) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(message) => println!("{}", message),
+        }
+        previous_command = line;
+    }
+}
@@ -0,0 +1,32 @@
+use aoc2019::intcode::network::Network;
+use itertools::Itertools;
+use std::env;
+use std::process::exit;
+
+// runs a network topology file and reports where each node ended up
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 2 {
+        eprintln!("Usage: intcode-net TOPOLOGY_FILE");
+        exit(2);
+    }
+    let mut network = Network::load(&args[1]).unwrap_or_else(|err| {
+        eprintln!("{}: {}", args[1], err);
+        exit(1);
+    });
+    let result = network.run();
+    for name in network.names() {
+        let program = network.program(name);
+        println!(
+            "{}: {:?}, outputs [{}], inputs [{}]",
+            name,
+            program.state(),
+            program.outputs().join(", "),
+            program.inputs().join(", ")
+        );
+    }
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        exit(1);
+    }
+}
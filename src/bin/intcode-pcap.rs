@@ -0,0 +1,114 @@
+use aoc2019::intcode::{capture, fail};
+use itertools::Itertools;
+use std::env;
+
+const USAGE: &str =
+    "Usage: intcode-pcap CAPTURE_FILE [--address ADDRESS] [--nat ADDRESS] [--summary]";
+
+fn main() {
+    let mut path = None;
+    let mut address: Option<isize> = None;
+    let mut nat = 255;
+    let mut summary_only = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut number = || -> isize {
+            let value = args.next().unwrap_or_else(|| fail(USAGE));
+            value
+                .parse()
+                .unwrap_or_else(|err| fail(format!("{}: {}", value, err)))
+        };
+        match arg.as_str() {
+            "--address" => address = Some(number()),
+            "--nat" => nat = number(),
+            "--summary" => summary_only = true,
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+    let Some(path) = path else {
+        fail(USAGE);
+    };
+    let captures = capture::load(&path).unwrap_or_else(|err| fail(format!("{}: {}", path, err)));
+
+    let selected = captures.iter().filter(|capture| {
+        address.is_none_or(|address| {
+            capture.packet.source == address || capture.packet.destination == address
+        })
+    });
+    if !summary_only {
+        for capture in selected.clone() {
+            let packet = &capture.packet;
+            println!(
+                "{:>6}: {:>4} -> {:<4} [{}]",
+                capture.round,
+                packet.source,
+                packet.destination,
+                packet.payload.iter().join(", ")
+            );
+        }
+    }
+    let rounds = captures.last().map_or(0, |capture| capture.round + 1);
+    println!(
+        "{} packets selected, {} in total over {} rounds",
+        selected.count(),
+        captures.len(),
+        rounds
+    );
+
+    // NAT activity: what it was sent, and what it sent on when the network went idle
+    let received: Vec<_> = captures
+        .iter()
+        .filter(|capture| capture.packet.destination == nat)
+        .collect();
+    let sent: Vec<_> = captures
+        .iter()
+        .filter(|capture| capture.packet.source == nat)
+        .collect();
+    if received.is_empty() && sent.is_empty() {
+        return;
+    }
+    println!(
+        "NAT {}: received {} packets from {} nodes, sent {}",
+        nat,
+        received.len(),
+        received
+            .iter()
+            .map(|capture| capture.packet.source)
+            .unique()
+            .count(),
+        sent.len()
+    );
+    for capture in &sent {
+        let packet = &capture.packet;
+        let last_received = received
+            .iter()
+            .take_while(|received| received.round <= capture.round)
+            .last()
+            .map_or(String::new(), |received| {
+                format!(" (last received in round {})", received.round)
+            });
+        println!(
+            "{:>6}: idle, sent [{}] to {}{}",
+            capture.round,
+            packet.payload.iter().join(", "),
+            packet.destination,
+            last_received
+        );
+    }
+    if let Some((a, b)) = sent
+        .iter()
+        .tuple_windows()
+        .find(|(a, b)| a.packet.payload.last() == b.packet.payload.last())
+    {
+        println!(
+            "first repeated value: {} (rounds {} and {})",
+            b.packet
+                .payload
+                .last()
+                .map_or(String::new(), |value| value.to_string()),
+            a.round,
+            b.round
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use aoc2019::intcode::io::{AsciiInput, AsciiOutput};
+use aoc2019::intcode::syscall::Syscalls;
+use aoc2019::intcode::{self, fail, Program, ProgramState::*};
+use std::env;
+use std::io::{self, BufReader};
+
+const USAGE: &str = "\
+Usage: intcode-script PROGRAM_FILE [--allow-files DIR]
+
+Runs a program with the standard host functions available through the SYS
+instruction, reading ASCII input from stdin and writing its output to stdout.
+With --allow-files, it may also read the files under DIR.";
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut path = None;
+    let mut syscalls = Syscalls::standard();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--allow-files" => syscalls.allow_files(args.next().unwrap_or_else(|| fail(USAGE))),
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+    let Some(path) = path else {
+        fail(USAGE);
+    };
+    let intcode = intcode::load(&path).unwrap_or_else(|err| fail(format!("{}: {}", path, err)));
+    let mut program = Program::builder(&intcode).syscalls(&syscalls).build();
+    program.set_input(Box::new(AsciiInput::new(BufReader::new(io::stdin()))));
+    program.set_output(Box::new(AsciiOutput::new(io::stdout())));
+    if let Err(err) = program.run() {
+        fail(err);
+    }
+    if let Some(mut output) = program.take_output() {
+        output.finish().unwrap_or_else(|err| fail(err));
+    }
+    if program.state() == WaitingForInput {
+        fail("Program is waiting for more input");
+    }
+}
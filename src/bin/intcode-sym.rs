@@ -0,0 +1,68 @@
+use aoc2019::intcode::symbolic::{SymbolicProgram, Target};
+use aoc2019::intcode::{self, fail};
+use std::env;
+
+const USAGE: &str = "\
+Usage: intcode-sym PROGRAM_FILE [--address ADDR | --output VALUE | --exit]
+                   [--input INDEX=VALUE]... [--max-steps N] [--max-paths N]
+
+Prints the conditions on the inputs (in0, in1, ...) for each way the program
+can reach the target, by default exiting.";
+
+fn parse<T: std::str::FromStr>(arg: Option<String>) -> T {
+    let arg = arg.unwrap_or_else(|| fail(USAGE));
+    arg.parse()
+        .unwrap_or_else(|_| fail(format!("invalid number '{}'", arg)))
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut path = None;
+    let mut target = Target::Exit;
+    let mut inputs = vec![];
+    let (mut max_steps, mut max_paths) = (100_000, 10_000);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--address" => target = Target::Address(parse(args.next())),
+            "--output" => target = Target::Output(parse(args.next())),
+            "--exit" => target = Target::Exit,
+            "--input" => {
+                let input = args.next().unwrap_or_else(|| fail(USAGE));
+                let Some((index, value)) = input.split_once('=') else {
+                    fail(USAGE);
+                };
+                inputs.push((
+                    parse(Some(index.to_string())),
+                    parse(Some(value.to_string())),
+                ));
+            }
+            "--max-steps" => max_steps = parse(args.next()),
+            "--max-paths" => max_paths = parse(args.next()),
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+    let Some(path) = path else {
+        fail(USAGE);
+    };
+    let intcode = intcode::load(&path).unwrap_or_else(|err| fail(format!("{}: {}", path, err)));
+    let mut program = SymbolicProgram::new(&intcode);
+    program.set_limits(max_steps, max_paths);
+    for (index, value) in inputs {
+        program.set_input(index, value);
+    }
+    let exploration = program.explore(target);
+    for path in &exploration.reached {
+        println!("{}", path);
+    }
+    for (path, reason) in &exploration.abandoned {
+        println!("? {} ({})", path, reason);
+    }
+    eprintln!(
+        "{} paths reach the target, {} miss it, {} fault, {} abandoned",
+        exploration.reached.len(),
+        exploration.missed,
+        exploration.faulted,
+        exploration.abandoned.len()
+    );
+}
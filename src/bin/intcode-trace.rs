@@ -0,0 +1,97 @@
+use aoc2019::intcode::coverage::Coverage;
+use aoc2019::intcode::trace::{BinaryTracer, TextTracer, TraceReader, Tracer};
+use aoc2019::intcode::{self, fail, Program, ProgramState::*};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+
+const USAGE: &str = "\
+Usage: intcode-trace PROGRAM_FILE [INPUT...] [--binary TRACE_FILE]
+       intcode-trace PROGRAM_FILE [INPUT...] --profile
+       intcode-trace PROGRAM_FILE [INPUT...] --coverage SUMMARY_FILE
+       intcode-trace --decode TRACE_FILE";
+
+fn decode(path: &str) {
+    let file = File::open(path).unwrap_or_else(|err| fail(format!("{}: {}", path, err)));
+    let reader = TraceReader::new(BufReader::new(file)).unwrap_or_else(|err| fail(err));
+    let mut tracer = TextTracer::new(io::stdout().lock());
+    for event in reader {
+        event.unwrap_or_else(|err| fail(err)).replay(&mut tracer);
+    }
+    tracer.finish().unwrap_or_else(|err| fail(err));
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if args.len() == 2 && args[0] == "--decode" {
+        decode(&args[1]);
+        return;
+    }
+    let profile = match args.iter().position(|arg| arg == "--profile") {
+        Some(position) => {
+            args.remove(position);
+            true
+        }
+        None => false,
+    };
+    let mut path_option = |option: &str| match args.iter().position(|arg| arg == option) {
+        Some(position) if position + 1 < args.len() => {
+            let path = args.remove(position + 1);
+            args.remove(position);
+            Some(path)
+        }
+        Some(_) => fail(USAGE),
+        None => None,
+    };
+    let binary_path = path_option("--binary");
+    let coverage_path = path_option("--coverage");
+    if binary_path.is_some() && (profile || coverage_path.is_some()) {
+        fail(USAGE);
+    }
+    let Some((path, inputs)) = args.split_first() else {
+        fail(USAGE);
+    };
+    let intcode = intcode::load(path).unwrap_or_else(|err| fail(format!("{}: {}", path, err)));
+    let mut program = Program::new(&intcode);
+    for input in inputs {
+        program.send(
+            input
+                .parse()
+                .unwrap_or_else(|err| fail(format!("{}: {}", input, err))),
+        );
+    }
+    match &binary_path {
+        _ if profile || coverage_path.is_some() => program.start_profiling(),
+        Some(binary_path) => {
+            let file = File::create(binary_path)
+                .unwrap_or_else(|err| fail(format!("{}: {}", binary_path, err)));
+            let tracer = BinaryTracer::new(BufWriter::new(file)).unwrap_or_else(|err| fail(err));
+            program.set_tracer(Box::new(tracer));
+        }
+        None => program.set_tracer(Box::new(TextTracer::new(io::stdout()))),
+    }
+    while program.state() == Running {
+        if let Err(err) = program.step() {
+            fail(err);
+        }
+    }
+    if let Some(mut tracer) = program.take_tracer() {
+        tracer.finish().unwrap_or_else(|err| fail(err));
+    }
+    if program.state() == WaitingForInput {
+        eprintln!("Program is waiting for more input");
+    }
+    match (program.profile(), &coverage_path) {
+        (Some(profile), Some(coverage_path)) => {
+            let coverage = Coverage::from_profile(profile, &intcode);
+            print!("{}", coverage.listing(&intcode));
+            fs::write(coverage_path, coverage.summary(&intcode) + "\n")
+                .unwrap_or_else(|err| fail(format!("{}: {}", coverage_path, err)));
+        }
+        (Some(profile), None) => {
+            println!("{}", profile.summary(20));
+            print!("{}", profile.annotated_listing(&intcode));
+        }
+        _ => {}
+    }
+}
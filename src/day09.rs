@@ -1,23 +1,19 @@
-use hashbrown::HashMap;
-
 // this version of the intcode computer turned out to be the final one, so I
-// exported it to a separate module so I can reuse it later:
+// exported it to a separate module so I can reuse it later; the BOOST checks
+// run on 64-bit words whatever the platform, trapping on overflow
 use super::intcode::{Program, ProgramState::*};
 
-fn get_output(intcode: &[isize], input: isize) -> isize {
-    let mut progs = [Program::new(intcode)];
-    progs[0].send(input);
-    Program::run_until(&mut progs, &HashMap::new(), |progs: &[Program]| {
-        progs[0].state() == Exited
-    });
-    // dbg!(&progs[0].outputs);
-    progs[0].receive().unwrap()
+fn get_output(intcode: &[i64], input: i64) -> i64 {
+    let mut program = Program::new(intcode);
+    program.send(input);
+    assert_eq!(program.run().unwrap(), Exited);
+    program.receive().unwrap()
 }
 
 pub fn run(input: &str) {
-    let intcode: Vec<isize> = input
+    let intcode: Vec<i64> = input
         .split(',')
-        .map(|n| n.parse::<isize>().unwrap())
+        .map(|n| n.parse::<i64>().unwrap())
         .collect();
     let boost_keycode = get_output(&intcode, 1);
     println!("{}", boost_keycode);
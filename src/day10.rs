@@ -60,7 +60,7 @@ fn laser_vaporize(asteroids: &[(isize, isize)], index_max: usize, target: usize)
     let mut asteroids: Vec<(isize, isize)> = asteroids
         .iter()
         .map(|(ox, oy)| (*ox - base_x, *oy - base_y))
-        .filter(|(ox, oy)| (*ox != 0 || *oy != 0))
+        .filter(|(ox, oy)| *ox != 0 || *oy != 0)
         .collect();
     asteroids.sort_by(|p1, p2| rotation_order_v2(p1.0, p1.1, p2.0, p2.1));
     let mut count = 0;
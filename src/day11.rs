@@ -17,7 +17,7 @@ fn paint_spaceship(intcode: &[isize], display: bool) -> usize {
         let is_white = *panels.get(&(x, y)).unwrap_or(&false);
         brain.send(is_white as isize);
         while brain.state() == Running && brain.num_outputs() < 2 {
-            brain.step();
+            brain.step().unwrap();
         }
         if brain.num_outputs() >= 2 {
             let is_paint_white = brain.receive().unwrap() == 1;
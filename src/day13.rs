@@ -62,8 +62,11 @@ fn display_game(screen: &HashMap<(isize, isize), Tile>, score: usize) {
     println!();
 }
 
-fn run_game(intcode: &[isize], beat_game: bool, display: bool) -> usize {
+fn run_game(intcode: &[isize], beat_game: bool, display: bool, profile: bool) -> usize {
     let mut game = Program::new(intcode);
+    if profile {
+        game.start_profiling();
+    }
     if beat_game {
         game.write(0, 2); // no need for quarters
     }
@@ -73,7 +76,7 @@ fn run_game(intcode: &[isize], beat_game: bool, display: bool) -> usize {
     let mut paddle_x = 0;
     let mut score = 0;
     while game.state() != Exited {
-        game.step();
+        game.step().unwrap();
         while game.num_outputs() >= 3 {
             let x = game.receive().unwrap();
             let y = game.receive().unwrap();
@@ -108,6 +111,9 @@ fn run_game(intcode: &[isize], beat_game: bool, display: bool) -> usize {
             display_game(&screen, score);
         }
     }
+    if let Some(profile) = game.profile() {
+        eprintln!("{}", profile.summary(20));
+    }
     if beat_game {
         score
     } else {
@@ -120,8 +126,9 @@ pub fn run(input: &str) {
         .split(',')
         .map(|n| n.parse::<isize>().unwrap())
         .collect();
-    let num_blocks = run_game(&intcode, false, false);
+    let num_blocks = run_game(&intcode, false, false, false);
     println!("{}", num_blocks);
-    let score = run_game(&intcode, true, false);
+    let profile = false;
+    let score = run_game(&intcode, true, false, profile);
     println!("{}", score);
 }
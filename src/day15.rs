@@ -21,9 +21,7 @@ impl Robot {
 
     fn maybe_move(&mut self, direction: isize) -> isize {
         self.brain.send(direction);
-        while self.brain.num_outputs() == 0 {
-            self.brain.step();
-        }
+        self.brain.run_until_output().unwrap();
         let output = self.brain.receive().unwrap();
         let index = (direction - 1) as usize;
         if output != 0 {
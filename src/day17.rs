@@ -1,76 +1,12 @@
 use itertools::Itertools;
 
-use super::intcode::{Program, ProgramState::*};
+use super::intcode::{ascii::AsciiConsole, Program};
 use hashbrown::HashSet;
 
-struct AsciiRobot {
-    brain: Program,
-    video_feed: bool,
-    ansi_terminal: bool,
-    next_home: bool,
-    last_char: u8,
-    final_output: isize,
-}
-
-fn ansi_terminal_home() {
-    print!("\x1b[1;1H"); // ANSI Code to jump to top left corner
-}
-
-fn ansi_terminal_clear() {
-    print!("\x1b[2J"); // ANSI Code to jump to top left corner
-    ansi_terminal_home();
-}
-
-impl AsciiRobot {
-    fn new(intcode: &[isize], video_feed: bool, ansi_terminal: bool) -> Self {
-        Self {
-            brain: Program::new(intcode),
-            video_feed,
-            ansi_terminal,
-            next_home: true,
-            last_char: 0,
-            final_output: 0,
-        }
-    }
-
-    fn step(&mut self) {
-        self.brain.step();
-        while self.brain.num_outputs() > 0 {
-            let val = self.brain.receive().unwrap();
-            if val > 127 {
-                self.final_output = val;
-            } else if self.video_feed {
-                let val = val as u8;
-                if self.ansi_terminal {
-                    if self.last_char == b'\n' && val == b'\n' {
-                        self.next_home = true;
-                    } else {
-                        if self.next_home {
-                            ansi_terminal_home();
-                            self.next_home = false;
-                        }
-                        print!("{}", val as char);
-                    }
-                } else {
-                    print!("{}", val as char);
-                }
-                self.last_char = val;
-            }
-        }
-    }
-}
-
 fn get_map(intcode: &[isize]) -> Vec<Vec<u8>> {
-    let mut robot = Program::new(intcode);
-    while robot.state() == Running {
-        robot.step();
-    }
-    let mut data: Vec<u8> = vec![];
-    while robot.num_outputs() > 0 {
-        let c = robot.receive().unwrap() as u8;
-        data.push(c);
-    }
-    String::from_utf8(data)
+    let mut camera = AsciiConsole::new(Program::new(intcode));
+    camera
+        .read_all()
         .unwrap()
         .lines()
         .map(|s| s.bytes().collect())
@@ -243,36 +179,23 @@ fn find_routines(map: &[Vec<u8>]) -> String {
     recursive_search(&sequences, &path, 0, &[], &[]).unwrap()
 }
 
-fn save_robots(robot: &mut AsciiRobot, map: &[Vec<u8>]) -> usize {
-    robot.brain.write(0, 2); // wake up
+fn save_robots(robot: &mut AsciiConsole, map: &[Vec<u8>], video_feed: bool) -> usize {
+    robot.program().write(0, 2); // wake up
     let routines = find_routines(map);
     let mut answers: Vec<_> = routines.lines().collect();
-    answers.push(if robot.video_feed { "y" } else { "n" });
+    answers.push(if video_feed { "y" } else { "n" });
 
     for answer in answers {
-        while robot.brain.state() == Running {
-            robot.step();
-        } // wait for prompt
-        if robot.video_feed {
-            println!("{}", answer);
-        }
-        for b in answer.bytes() {
-            robot.brain.send(b as isize);
-        } // send answer
-        robot.brain.send(b'\n' as isize); // line feed
-        robot.step();
+        robot.read_until_prompt().unwrap();
+        robot.send_line(answer);
     }
 
-    if robot.video_feed {
-        ansi_terminal_clear();
-    }
-    while robot.brain.state() == Running {
-        robot.step();
-    }
-    if robot.video_feed {
+    robot.clear_screen();
+    robot.read_all().unwrap();
+    if video_feed {
         println!();
     }
-    robot.final_output as usize
+    robot.final_value().unwrap() as usize
 }
 
 pub fn run(input: &str) {
@@ -286,7 +209,9 @@ pub fn run(input: &str) {
 
     let video_feed = false;
     let ansi_terminal = false;
-    let mut robot = AsciiRobot::new(&intcode, video_feed, ansi_terminal);
-    let dust_amount = save_robots(&mut robot, &map);
+    let mut robot = AsciiConsole::new(Program::new(&intcode));
+    robot.set_echo(video_feed);
+    robot.set_ansi(ansi_terminal);
+    let dust_amount = save_robots(&mut robot, &map, video_feed);
     println!("{}", dust_amount);
 }
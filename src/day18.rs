@@ -35,10 +35,10 @@ fn shortest_distance(
                 if c == b'#' {
                     return false;
                 } // wall
-                if (b'A'..=b'Z').contains(&c) && !keys.contains(&(c - b'A' + b'a')) {
+                if c.is_ascii_uppercase() && !keys.contains(&(c - b'A' + b'a')) {
                     return false;
                 } // cannot open the door
-                if (x, y) != target && (b'a'..=b'z').contains(&c) && !keys.contains(&c) {
+                if (x, y) != target && c.is_ascii_lowercase() && !keys.contains(&c) {
                     return false;
                 } // cannot pickup other key than target
                 true
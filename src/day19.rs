@@ -1,22 +1,33 @@
-use crate::intcode::Program;
+use crate::intcode::compiled::CompiledProgram;
+use crate::intcode::symbolic::{Exploration, SymbolicProgram, Target};
+use crate::intcode::{Engine, ProgramState::*};
 
-fn in_beam(intcode: &[isize], x: usize, y: usize) -> bool {
-    let mut program = Program::new(intcode);
-    program.send(x as isize);
-    program.send(y as isize);
-    while program.num_outputs() == 0 {
-        program.step()
+fn deploy(drone: &mut impl Engine, x: usize, y: usize) -> bool {
+    drone.send(x as isize);
+    drone.send(y as isize);
+    while drone.num_outputs() == 0 && drone.state() == Running {
+        drone.step().unwrap();
     }
-    let output = program.receive().unwrap();
+    let output = drone.receive().unwrap();
     assert!(output == 0 || output == 1);
     output == 1
 }
 
-fn count_in_beam_50x50(intcode: &[isize], display: bool) -> usize {
+// beam holds the inputs for which the program outputs 1, worked out once by
+// symbolic execution; drones only get deployed for inputs it cannot decide,
+// each spawned from the same one so they share the code they compile
+fn in_beam(drone: &CompiledProgram, beam: &Exploration, x: usize, y: usize) -> bool {
+    if let Some(in_beam) = beam.reaches(&[x as isize, y as isize]) {
+        return in_beam;
+    }
+    deploy(&mut drone.spawn(), x, y)
+}
+
+fn count_in_beam_50x50(drone: &CompiledProgram, beam: &Exploration, display: bool) -> usize {
     let mut count = 0;
     for y in 0..50 {
         for x in 0..50 {
-            if in_beam(intcode, x, y) {
+            if in_beam(drone, beam, x, y) {
                 if display {
                     print!("#");
                 }
@@ -32,12 +43,12 @@ fn count_in_beam_50x50(intcode: &[isize], display: bool) -> usize {
     count
 }
 
-fn closest_100x100_in_beam(intcode: &[isize]) -> usize {
+fn closest_100x100_in_beam(drone: &CompiledProgram, beam: &Exploration) -> usize {
     let (mut x, mut y) = (0, 0);
     loop {
-        if in_beam(intcode, x + 100 - 1, y) {
+        if in_beam(drone, beam, x + 100 - 1, y) {
             // top right
-            if in_beam(intcode, x, y + 100 - 1) {
+            if in_beam(drone, beam, x, y + 100 - 1) {
                 // lower left
                 return x * 10000 + y;
             } else {
@@ -55,8 +66,10 @@ pub fn run(input: &str) {
         .map(|n| n.parse::<isize>().unwrap())
         .collect();
     let display = false;
-    let count = count_in_beam_50x50(&intcode, display);
+    let beam = SymbolicProgram::new(&intcode).explore(Target::Output(1));
+    let drone = CompiledProgram::new(&intcode);
+    let count = count_in_beam_50x50(&drone, &beam, display);
     println!("{}", count);
-    let location = closest_100x100_in_beam(&intcode);
+    let location = closest_100x100_in_beam(&drone, &beam);
     println!("{}", location);
 }
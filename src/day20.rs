@@ -9,17 +9,17 @@ fn get_portals_and_named_locations(maze: &[Vec<u8>]) -> (Portals, NamedLocations
     let mut named_locations = HashMap::new();
     for (y, row) in maze.iter().enumerate() {
         for (x, c) in row.iter().enumerate() {
-            if (b'A'..=b'Z').contains(c) {
+            if c.is_ascii_uppercase() {
                 let mut c2 = 0;
                 let mut pos = (0, 0);
-                if x < row.len() - 1 && (b'A'..=b'Z').contains(&row[x + 1]) {
+                if x < row.len() - 1 && row[x + 1].is_ascii_uppercase() {
                     c2 = row[x + 1];
                     if x > 0 && row[x - 1] == b'.' {
                         pos = (x - 1, y);
                     } else {
                         pos = (x + 2, y)
                     }
-                } else if y < maze.len() - 1 && (b'A'..=b'Z').contains(&maze[y + 1][x]) {
+                } else if y < maze.len() - 1 && maze[y + 1][x].is_ascii_uppercase() {
                     c2 = maze[y + 1][x];
                     if y > 0 && maze[y - 1][x] == b'.' {
                         pos = (x, y - 1);
@@ -12,7 +12,7 @@ impl Shuffle {
         if line == "deal into new stack" {
             return DealIntoNewStack;
         }
-        let number_str = line.split(' ').last().unwrap();
+        let number_str = line.split(' ').next_back().unwrap();
         if line.starts_with("cut ") {
             let n: isize = number_str.parse().unwrap();
             if n > 0 {
@@ -1,47 +1,8 @@
-use crate::intcode::{Program, ProgramState::*};
+use crate::intcode::{ascii::AsciiConsole, Program};
 use itertools::Itertools;
 use regex::Regex;
 use std::io::{self, BufRead};
 
-struct Droid {
-    brain: Program,
-    echo: bool,
-}
-
-impl Droid {
-    fn new(intcode: &[isize], echo: bool) -> Self {
-        Self {
-            brain: Program::new(intcode),
-            echo,
-        }
-    }
-
-    fn run_until_next_command(&mut self) -> String {
-        let mut outputs = vec![];
-        loop {
-            self.brain.step();
-            while let Some(output) = self.brain.receive() {
-                let output = output as u8;
-                if self.echo {
-                    print!("{}", output as char);
-                }
-                outputs.push(output);
-            }
-            if self.brain.state() != Running {
-                break;
-            }
-        }
-        String::from_utf8(outputs).unwrap()
-    }
-
-    fn send_command(&mut self, command: &str) {
-        for b in command.bytes() {
-            self.brain.send(b as isize);
-        }
-        self.brain.send(b'\n' as isize);
-    }
-}
-
 pub fn run(input: &str) {
     let intcode: Vec<_> = input
         .split(',')
@@ -50,14 +11,19 @@ pub fn run(input: &str) {
 
     let manual = false;
     let debug = false;
-    let mut droid = Droid::new(&intcode, debug);
+    let profile = false;
+    let mut droid = AsciiConsole::new(Program::new(&intcode));
+    droid.set_echo(debug);
+    if profile {
+        droid.program().start_profiling();
+    }
 
     if manual {
         let stdin = io::stdin();
         loop {
-            droid.run_until_next_command();
+            droid.read_until_prompt().unwrap();
             let command = stdin.lock().lines().next().unwrap().unwrap();
-            droid.send_command(&command);
+            droid.send_line(&command);
         }
     } else {
         let commands = [
@@ -84,8 +50,8 @@ pub fn run(input: &str) {
             "west",
         ];
         for command in commands {
-            droid.run_until_next_command();
-            droid.send_command(command);
+            droid.read_until_prompt().unwrap();
+            droid.send_line(command);
         }
 
         let items = [
@@ -106,20 +72,23 @@ pub fn run(input: &str) {
         'outer: for length in 0..=items.len() {
             for combination in items.iter().combinations(length) {
                 for item in &combination {
-                    droid.run_until_next_command();
-                    droid.send_command(&format!("drop {}", item));
+                    droid.read_until_prompt().unwrap();
+                    droid.send_line(&format!("drop {}", item));
                 }
-                droid.run_until_next_command();
-                droid.send_command("west");
+                droid.read_until_prompt().unwrap();
+                droid.send_line("west");
                 for item in &combination {
-                    let output = droid.run_until_next_command();
+                    let output = droid.read_until_prompt().unwrap();
                     if let Some(cap) = regex.captures_iter(&output).next() {
                         println!("{}", &cap[1]);
                         break 'outer;
                     }
-                    droid.send_command(&format!("take {}", item));
+                    droid.send_line(&format!("take {}", item));
                 }
             }
         }
     }
+    if let Some(profile) = droid.program().profile() {
+        eprintln!("{}", profile.summary(20));
+    }
 }
@@ -1,89 +1,389 @@
 use hashbrown::HashMap;
+use memory::{Memory, MemoryKind};
 use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-#[derive(Eq, PartialEq, Copy, Clone)]
+pub mod ascii;
+pub mod asm;
+pub mod builder;
+pub mod capture;
+pub mod cfg;
+pub mod compiled;
+pub mod coverage;
+pub mod disasm;
+pub mod history;
+pub mod io;
+pub mod memory;
+pub mod network;
+pub mod opcodes;
+pub mod packet;
+pub mod profile;
+pub mod runtime;
+pub mod snapshot;
+pub mod symbolic;
+pub mod syscall;
+pub mod task;
+pub mod trace;
+mod varint;
+pub mod word;
+
+use builder::ProgramBuilder;
+use history::{History, Step};
+use io::{IntcodeInput, IntcodeOutput};
+use opcodes::{OpcodeRegistry, Operands};
+use profile::Profile;
+use snapshot::Snapshot;
+use trace::Tracer;
+use word::{OverflowPolicy, Word};
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
 pub enum ProgramState {
     Running,
     WaitingForInput,
     Exited,
+    Faulted,
+    BudgetExhausted, // out of instructions or past the deadline, see set_budget()
 }
 
 use ProgramState::*;
 
-pub struct Program {
-    memory: HashMap<usize, isize>,
+// every error carries the instruction pointer and the raw op code of the
+// instruction that caused it
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum IntcodeError {
+    InvalidOpCode {
+        index: usize,
+        op_code: isize,
+    },
+    InvalidParameterMode {
+        index: usize,
+        op_code: isize,
+        mode: isize,
+    },
+    NegativeAddress {
+        index: usize,
+        op_code: isize,
+        address: isize,
+    },
+    WriteToImmediate {
+        index: usize,
+        op_code: isize,
+    },
+    Overflow {
+        index: usize,
+        op_code: isize,
+    },
+    Trap {
+        index: usize,
+        op_code: isize,
+        code: isize, // chosen by the registered instruction raising it
+    },
+}
+
+use IntcodeError::*;
+
+impl IntcodeError {
+    pub fn index(&self) -> usize {
+        match *self {
+            InvalidOpCode { index, .. }
+            | InvalidParameterMode { index, .. }
+            | NegativeAddress { index, .. }
+            | WriteToImmediate { index, .. }
+            | Overflow { index, .. }
+            | Trap { index, .. } => index,
+        }
+    }
+
+    pub fn op_code(&self) -> isize {
+        match *self {
+            InvalidOpCode { op_code, .. }
+            | InvalidParameterMode { op_code, .. }
+            | NegativeAddress { op_code, .. }
+            | WriteToImmediate { op_code, .. }
+            | Overflow { op_code, .. }
+            | Trap { op_code, .. } => op_code,
+        }
+    }
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvalidOpCode { op_code, .. } => write!(f, "invalid op code {}", op_code)?,
+            InvalidParameterMode { mode, .. } => write!(f, "invalid parameter mode {}", mode)?,
+            NegativeAddress { address, .. } => write!(f, "negative address {}", address)?,
+            WriteToImmediate { .. } => write!(f, "write to immediate mode parameter")?,
+            Overflow { .. } => write!(f, "arithmetic overflow")?,
+            Trap { code, .. } => write!(f, "trap {}", code)?,
+        }
+        write!(f, " at &{} (op code {})", self.index(), self.op_code())
+    }
+}
+
+impl std::error::Error for IntcodeError {}
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct OpInfo {
+    pub mnemonic: &'static str,
+    pub num_args: usize,
+    pub result_arg: Option<usize>, // index of the argument that gets written to
+}
+
+pub fn op_info(op: isize) -> Option<OpInfo> {
+    let (mnemonic, num_args, result_arg) = match op {
+        1 => ("ADD", 3, Some(2)), // add
+        2 => ("MUL", 3, Some(2)), // mul
+        3 => ("IN", 1, Some(0)),  // in
+        4 => ("OUT", 1, None),    // out
+        5 => ("JNZ", 2, None),    // jump if true
+        6 => ("JZ", 2, None),     // jump if false
+        7 => ("LT", 3, Some(2)),  // less than
+        8 => ("EQ", 3, Some(2)),  // equals
+        9 => ("ARB", 1, None),    // adjust relative base
+        99 => ("HLT", 0, None),   // exit
+        _ => return None,
+    };
+    Some(OpInfo {
+        mnemonic,
+        num_args,
+        result_arg,
+    })
+}
+
+// reads a program file of comma separated numbers
+pub fn load<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Vec<isize>> {
+    let source = std::fs::read_to_string(path)?;
+    source
+        .trim_end()
+        .split(',')
+        .map(|n| n.trim().parse::<isize>())
+        .collect::<Result<_, _>>()
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+}
+
+// for the command line tools: prints the message and exits with status 1
+pub fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+// the API shared by the execution engines, so solvers can switch engines
+pub trait Engine {
+    fn send(&mut self, value: isize);
+    fn receive(&mut self) -> Option<isize>;
+    fn num_outputs(&self) -> usize;
+    fn state(&self) -> ProgramState;
+    fn step(&mut self) -> Result<(), IntcodeError>;
+    fn read(&self, address: usize) -> isize;
+    fn write(&mut self, address: usize, value: isize);
+}
+
+// an instruction with its parameter modes and raw parameters, which only
+// depend on the memory words it spans
+#[derive(Copy, Clone)]
+struct Decoded<W = isize> {
+    op: isize,
+    op_code: isize,
+    num_args: usize,
+    modes: [isize; 3],
+    params: [W; 3],
+}
+
+const MAX_CACHED_INDEX: usize = 1 << 20;
+
+// Computes with isize words unless told otherwise, e.g. Program<i64> for the
+// same results on every platform, or Program<BigInt> to never overflow.
+// Addresses and relative base offsets still have to fit in an isize, and op
+// codes that do not are reported as op code 0. Snapshots, CompiledProgram and
+// the Engine trait only work with isize words.
+pub struct Program<W: Word = isize> {
+    memory: Memory<W>,
     index: usize,
-    inputs: VecDeque<isize>,
-    outputs: VecDeque<isize>,
+    inputs: VecDeque<W>,
+    outputs: VecDeque<W>,
     state: ProgramState,
     relative_base: isize,
+    fault: Option<IntcodeError>,
+    tracer: Option<Box<dyn Tracer<W> + Send>>,
+    input: Option<Box<dyn IntcodeInput<W> + Send>>, // read once inputs is empty
+    output: Option<Box<dyn IntcodeOutput<W> + Send>>, // replaces outputs
+    decoded: Vec<Option<Decoded<W>>>,               // cache, indexed by instruction address
+    budget: Option<usize>,                          // instructions left to execute
+    deadline: Option<Instant>,
+    num_steps: usize,           // instructions executed so far
+    next_deadline_check: usize, // in number of steps
+    profile: Option<Box<Profile>>,
+    history: Option<Box<History<W>>>,
+    opcodes: Option<Arc<OpcodeRegistry<W>>>, // instructions added by ProgramBuilder
+    overflow: OverflowPolicy,                // of ADD and MUL
 }
 
-impl Program {
-    fn _parse_op_code(op_code: isize) -> (isize, Vec<isize>) {
+// how often step() looks at the clock when there is a deadline
+const STEPS_PER_DEADLINE_CHECK: usize = 256;
+
+impl<W: Word> Program<W> {
+    // None for an address that does not fit in an isize
+    fn _to_address(&self, address: Option<isize>, op_code: isize) -> Result<usize, IntcodeError> {
+        let index = self.index;
+        match address {
+            Some(address) if address >= 0 => Ok(address as usize),
+            Some(address) => Err(NegativeAddress {
+                index,
+                op_code,
+                address,
+            }),
+            None => Err(Overflow { index, op_code }),
+        }
+    }
+
+    fn _decode(&self) -> Result<Decoded<W>, IntcodeError> {
+        self._decode_at(self.index)
+    }
+
+    fn _decode_at(&self, index: usize) -> Result<Decoded<W>, IntcodeError> {
+        let op_code = self.read(index).to_isize().unwrap_or(0);
         let op = op_code % 100;
-        let mut modes = op_code / 100;
-        let mut param_modes: Vec<isize> = vec![];
-        while modes > 0 {
-            param_modes.push(modes % 10);
-            modes /= 10;
+        let OpInfo {
+            num_args,
+            result_arg,
+            ..
+        } = self._op_info(op).ok_or(InvalidOpCode { index, op_code })?;
+        let mut modes = [0; 3];
+        let mut params = [W::zero(), W::zero(), W::zero()];
+        let mut mode_digits = op_code / 100;
+        for arg_index in 0..num_args {
+            let mode = mode_digits % 10;
+            mode_digits /= 10;
+            match mode {
+                1 if result_arg == Some(arg_index) => {
+                    return Err(WriteToImmediate { index, op_code })
+                }
+                0..=2 => {}
+                _ => {
+                    return Err(InvalidParameterMode {
+                        index,
+                        op_code,
+                        mode,
+                    })
+                }
+            }
+            modes[arg_index] = mode;
+            params[arg_index] = self.read(index + arg_index + 1);
         }
-        (op, param_modes)
+        Ok(Decoded {
+            op,
+            op_code,
+            num_args,
+            modes,
+            params,
+        })
     }
 
-    fn _get_args(&self) -> (isize, Vec<usize>, usize) {
-        let (op, param_modes) = Program::_parse_op_code(self.read(self.index));
-        let num_args = match op {
-            1 => 3,  // add
-            2 => 3,  // mul
-            3 => 1,  // in
-            4 => 1,  // out
-            5 => 2,  // jump if true
-            6 => 2,  // jump if false
-            7 => 3,  // less than
-            8 => 3,  // equals
-            9 => 1,  // adjust relative base
-            99 => 0, // exit
-            _ => panic!("Invalid op code {}", op),
-        };
-        let args = (0..num_args)
-            .map(|arg_index| {
-                let param_mode = *param_modes.get(arg_index).unwrap_or(&0);
-                match param_mode {
-                    0 => self.read(self.index + arg_index + 1) as usize,
-                    1 => self.index + arg_index + 1,
-                    2 => (self.relative_base + self.read(self.index + arg_index + 1)) as usize,
-                    _ => panic!("Invalid parameter mode {}", param_mode),
+    // like _decode(), but goes through the cache of decoded instructions
+    fn _fetch(&mut self) -> Result<Decoded<W>, IntcodeError> {
+        if let Some(Some(decoded)) = self.decoded.get(self.index) {
+            return Ok(decoded.clone());
+        }
+        let decoded = self._decode()?;
+        if self.index < MAX_CACHED_INDEX {
+            if self.index >= self.decoded.len() {
+                self.decoded.resize(self.index + 1, None);
+            }
+            self.decoded[self.index] = Some(decoded.clone());
+        }
+        Ok(decoded)
+    }
+
+    // argument addresses, which depend on the relative base
+    fn _resolve(&self, decoded: &Decoded<W>) -> Result<[usize; 3], IntcodeError> {
+        let mut args = [0; 3];
+        for (arg_index, arg) in args.iter_mut().enumerate().take(decoded.num_args) {
+            let param = decoded.params[arg_index].to_isize();
+            *arg = match decoded.modes[arg_index] {
+                0 => self._to_address(param, decoded.op_code)?,
+                1 => self.index + arg_index + 1,
+                _ => {
+                    let address = param.and_then(|offset| self.relative_base.checked_add(offset));
+                    self._to_address(address, decoded.op_code)?
                 }
-            })
-            .collect();
-        (op, args, 1 + num_args)
+            };
+        }
+        Ok(args)
     }
 
-    pub fn new(intcode: &[isize]) -> Self {
+    pub fn new(intcode: &[W]) -> Self {
+        Program::with_memory(intcode, MemoryKind::Dense)
+    }
+
+    pub fn with_memory(intcode: &[W], memory_kind: MemoryKind) -> Self {
         Self {
-            memory: intcode.iter().enumerate().map(|(i, v)| (i, *v)).collect(),
+            memory: Memory::new(memory_kind, intcode),
             index: 0,
             inputs: VecDeque::new(),
             outputs: VecDeque::new(),
             state: Running,
             relative_base: 0,
+            fault: None,
+            tracer: None,
+            input: None,
+            output: None,
+            decoded: vec![None; intcode.len()],
+            budget: None,
+            deadline: None,
+            num_steps: 0,
+            next_deadline_check: 0,
+            profile: None,
+            history: None,
+            opcodes: None,
+            overflow: OverflowPolicy::Trap,
+        }
+    }
+
+    pub fn builder(intcode: &[W]) -> ProgramBuilder<W> {
+        ProgramBuilder::new(intcode)
+    }
+
+    // instructions added to the built-in ones, if any
+    pub fn opcodes(&self) -> Option<&OpcodeRegistry<W>> {
+        self.opcodes.as_deref()
+    }
+
+    pub fn set_overflow(&mut self, overflow: OverflowPolicy) {
+        self.overflow = overflow;
+    }
+
+    pub fn overflow(&self) -> OverflowPolicy {
+        self.overflow
+    }
+
+    fn _op_info(&self, op: isize) -> Option<OpInfo> {
+        match &self.opcodes {
+            Some(opcodes) => opcodes.info(op),
+            None => op_info(op),
         }
     }
 
-    pub fn read(&self, address: usize) -> isize {
-        *self.memory.get(&address).unwrap_or(&0)
+    pub fn read(&self, address: usize) -> W {
+        self.memory.read(address)
     }
 
-    pub fn write(&mut self, address: usize, value: isize) {
-        self.memory.insert(address, value);
+    pub fn write(&mut self, address: usize, value: W) {
+        self.memory.write(address, value);
+        // forget decoded instructions that span this address
+        for index in address.saturating_sub(3)..=address {
+            if let Some(decoded) = self.decoded.get_mut(index) {
+                *decoded = None;
+            }
+        }
     }
 
-    pub fn send(&mut self, value: isize) {
+    pub fn send(&mut self, value: W) {
         self.inputs.push_front(value);
     }
 
-    pub fn receive(&mut self) -> Option<isize> {
+    pub fn receive(&mut self) -> Option<W> {
         self.outputs.pop_back()
     }
 
@@ -95,104 +395,509 @@ impl Program {
         self.state
     }
 
-    pub fn step(&mut self) {
-        if self.state == Exited {
-            return;
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn relative_base(&self) -> isize {
+        self.relative_base
+    }
+
+    // pending inputs, in the order the program will read them
+    pub fn inputs(&self) -> impl Iterator<Item = &W> {
+        self.inputs.iter().rev()
+    }
+
+    // pending outputs, in the order receive() will return them
+    pub fn outputs(&self) -> impl Iterator<Item = &W> {
+        self.outputs.iter().rev()
+    }
+
+    // op and resolved argument addresses of the next instruction to execute
+    pub fn instruction(&self) -> Result<(isize, Vec<usize>), IntcodeError> {
+        let decoded = self._decode()?;
+        let args = self._resolve(&decoded)?;
+        Ok((decoded.op, args[..decoded.num_args].to_vec()))
+    }
+
+    fn _fault(&mut self, error: IntcodeError) -> Result<(), IntcodeError> {
+        self.state = Faulted;
+        self.fault = Some(error);
+        if let Some(tracer) = &mut self.tracer {
+            tracer.halt(self.index, Faulted);
+        }
+        Err(error)
+    }
+
+    pub fn fault(&self) -> Option<IntcodeError> {
+        self.fault
+    }
+
+    pub fn set_tracer(&mut self, tracer: Box<dyn Tracer<W> + Send>) {
+        self.tracer = Some(tracer);
+    }
+
+    pub fn take_tracer(&mut self) -> Option<Box<dyn Tracer<W> + Send>> {
+        self.tracer.take()
+    }
+
+    // Limits how many more instructions the program may execute, and for how
+    // long, None meaning no limit. Once either runs out, step() does nothing
+    // and the program is left in the BudgetExhausted state; setting a new
+    // budget lets it carry on.
+    pub fn set_budget(&mut self, steps: Option<usize>, timeout: Option<Duration>) {
+        self.budget = steps;
+        self.deadline = timeout.map(|timeout| Instant::now() + timeout);
+        self.next_deadline_check = self.num_steps;
+        if self.state == BudgetExhausted {
+            self.state = Running;
+        }
+    }
+
+    // instructions executed so far
+    pub fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    // Looks at the clock every STEPS_PER_DEADLINE_CHECK instructions, and on
+    // every call while waiting for input since no instructions run then.
+    fn _budget_exhausted(&mut self) -> bool {
+        if self.budget == Some(0) {
+            return true;
+        }
+        match self.deadline {
+            Some(deadline)
+                if self.num_steps >= self.next_deadline_check || self.state == WaitingForInput =>
+            {
+                self.next_deadline_check = self.num_steps + STEPS_PER_DEADLINE_CHECK;
+                Instant::now() >= deadline
+            }
+            _ => false,
+        }
+    }
+
+    fn _count_steps(&mut self, num_steps: usize) {
+        self.num_steps += num_steps;
+        if let Some(budget) = &mut self.budget {
+            *budget = budget.saturating_sub(num_steps);
+        }
+    }
+
+    // steps until the program waits for input, exits or runs out of budget
+    pub fn run(&mut self) -> Result<ProgramState, IntcodeError> {
+        while self.state == Running {
+            self.step()?;
+        }
+        Ok(self.state)
+    }
+
+    // like run(), but also stops as soon as there is an output to receive
+    pub fn run_until_output(&mut self) -> Result<ProgramState, IntcodeError> {
+        while self.state == Running && self.outputs.is_empty() {
+            self.step()?;
+        }
+        Ok(self.state)
+    }
+
+    // starts counting executions, waits and memory accesses, from scratch
+    pub fn start_profiling(&mut self) {
+        self.profile = Some(Box::default());
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_deref()
+    }
+
+    // stops profiling
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.take().map(|profile| *profile)
+    }
+
+    // starts keeping an undo log of the last limit instructions executed, for
+    // step_back()
+    pub fn start_recording(&mut self, limit: usize) {
+        self.history = Some(Box::new(History::new(limit)));
+    }
+
+    pub fn history(&self) -> Option<&History<W>> {
+        self.history.as_deref()
+    }
+
+    pub fn stop_recording(&mut self) -> Option<History<W>> {
+        self.history.take().map(|history| *history)
+    }
+
+    // Undoes the last instruction recorded, returning false if there is none.
+    // An input it read is queued again, and an output it made is taken back
+    // unless it went to the output sink or has been received already.
+    pub fn step_back(&mut self) -> bool {
+        let Some(step) = self.history.as_mut().and_then(|history| history.pop()) else {
+            return false;
+        };
+        for (address, value) in step.writes.into_iter().rev() {
+            self.write(address, value);
+        }
+        if let Some(input) = step.input {
+            self.inputs.push_back(input);
+        }
+        if step.queued_output {
+            self.outputs.pop_front();
+        }
+        self.index = step.index;
+        self.relative_base = step.relative_base;
+        self.state = step.state;
+        self.fault = None;
+        self.num_steps = step.number;
+        self.budget = step.budget;
+        true
+    }
+
+    pub fn set_input(&mut self, input: Box<dyn IntcodeInput<W> + Send>) {
+        self.input = Some(input);
+    }
+
+    pub fn take_input(&mut self) -> Option<Box<dyn IntcodeInput<W> + Send>> {
+        self.input.take()
+    }
+
+    pub fn set_output(&mut self, output: Box<dyn IntcodeOutput<W> + Send>) {
+        self.output = Some(output);
+    }
+
+    pub fn take_output(&mut self) -> Option<Box<dyn IntcodeOutput<W> + Send>> {
+        self.output.take()
+    }
+
+    // values sent with send() come first, then whatever the input provides
+    fn _next_input(&mut self) -> Option<W> {
+        match self.inputs.pop_back() {
+            Some(value) => Some(value),
+            None => self.input.as_mut()?.next_input(),
+        }
+    }
+
+    fn _emit(&mut self, value: W) {
+        if let Some(step) = self.history.as_mut().and_then(|history| history.current()) {
+            step.output = Some(value.clone());
+            step.queued_output = self.output.is_none();
+        }
+        match &mut self.output {
+            Some(output) => output.output(value),
+            None => self.outputs.push_front(value),
+        }
+    }
+
+    // memory accesses made by the program itself, as opposed to read() and
+    // write() which let the host inspect memory without tracing
+    fn _load(&mut self, address: usize) -> W {
+        let value = self.read(address);
+        if let Some(profile) = &mut self.profile {
+            *profile.reads.entry(address).or_default() += 1;
+        }
+        if let Some(tracer) = &mut self.tracer {
+            tracer.read(address, value.clone());
+        }
+        value
+    }
+
+    fn _store(&mut self, address: usize, value: W) {
+        if let Some(step) = self.history.as_mut().and_then(|history| history.current()) {
+            step.writes.push((address, self.memory.read(address)));
+        }
+        if let Some(profile) = &mut self.profile {
+            *profile.writes.entry(address).or_default() += 1;
+        }
+        if let Some(tracer) = &mut self.tracer {
+            tracer.write(address, value.clone());
+        }
+        self.write(address, value);
+    }
+
+    pub fn step(&mut self) -> Result<(), IntcodeError> {
+        match self.state {
+            Exited | BudgetExhausted => return Ok(()),
+            Faulted => return Err(self.fault.unwrap()),
+            _ => {}
+        }
+        if self._budget_exhausted() {
+            self.state = BudgetExhausted;
+            return Ok(());
+        }
+        if let Some(history) = &mut self.history {
+            history.push(Step {
+                number: self.num_steps,
+                index: self.index,
+                relative_base: self.relative_base,
+                writes: vec![],
+                input: None,
+                output: None,
+                state: self.state,
+                budget: self.budget,
+                queued_output: false,
+            });
+        }
+        let decoded = match self._fetch() {
+            Ok(decoded) => decoded,
+            Err(error) => return self._fault(error),
+        };
+        let args = match self._resolve(&decoded) {
+            Ok(args) => args,
+            Err(error) => return self._fault(error),
+        };
+        let (op, op_code, offset) = (decoded.op, decoded.op_code, 1 + decoded.num_args);
+        let mut input = None;
+        if op == 3 {
+            input = self._next_input();
+            if input.is_none() {
+                if let Some(profile) = &mut self.profile {
+                    *profile.waits.entry(self.index).or_default() += 1;
+                }
+                if let Some(history) = &mut self.history {
+                    history.pop(); // nothing to undo
+                }
+                self.state = WaitingForInput;
+                return Ok(());
+            }
         }
+        let index = self.index;
+        self._count_steps(1);
+        if let Some(profile) = &mut self.profile {
+            *profile.executions.entry(index).or_default() += 1;
+            *profile.ops.entry(op).or_default() += 1;
+        }
+        if let Some(tracer) = &mut self.tracer {
+            let args = &args[..decoded.num_args];
+            let values: Vec<W> = args.iter().map(|arg| self.memory.read(*arg)).collect();
+            tracer.before_instruction(index, op_code, args, &values);
+        }
+        let overflow = Overflow { index, op_code };
         let mut increment_index = true;
-        let (op, args, offset) = self._get_args();
         match op {
             1 => {
                 // add
-                let result = self.read(args[0]) + self.read(args[1]);
-                // println!("&{} = {} + {}", args[2], self.read(args[0]), self.read(args[1]));
-                self.write(args[2], result);
+                let (a, b) = (self._load(args[0]), self._load(args[1]));
+                let Some(result) = a.add(&b, self.overflow) else {
+                    return self._fault(overflow);
+                };
+                self._store(args[2], result);
             }
             2 => {
                 // mul
-                let result = self.read(args[0]) * self.read(args[1]);
-                // println!("&{} = {} * {}", args[2], self.read(args[0]), self.read(args[1]));
-                self.write(args[2], result);
+                let (a, b) = (self._load(args[0]), self._load(args[1]));
+                let Some(result) = a.mul(&b, self.overflow) else {
+                    return self._fault(overflow);
+                };
+                self._store(args[2], result);
             }
             3 => {
                 // in
-                if let Some(input) = self.inputs.pop_back() {
-                    // println!("&{} = {} (in)", args[0], input);
-                    self.write(args[0], input);
-                } else {
-                    self.state = WaitingForInput;
-                    return;
+                let input = input.unwrap();
+                if let Some(step) = self.history.as_mut().and_then(|history| history.current()) {
+                    step.input = Some(input.clone());
+                }
+                if let Some(tracer) = &mut self.tracer {
+                    tracer.input(input.clone());
                 }
+                self._store(args[0], input);
             }
             4 => {
                 // out
-                // println!("{} (out)", self.read(args[0]));
-                self.outputs.push_front(self.read(args[0]));
-            }
-            5 => {
-                // jump if true
-                // println!("if {} != 0 goto &{}", self.read(args[0]), self.read(args[1]));
-                if self.read(args[0]) != 0 {
-                    self.index = self.read(args[1]) as usize;
-                    increment_index = false;
+                let output = self._load(args[0]);
+                if let Some(tracer) = &mut self.tracer {
+                    tracer.output(output.clone());
                 }
+                self._emit(output);
             }
-            6 => {
-                // jump if false
-                // println!("if {} == 0 goto &{}", self.read(args[0]), self.read(args[1]));
-                if self.read(args[0]) == 0 {
-                    self.index = self.read(args[1]) as usize;
+            5 | 6 => {
+                // jump if true, jump if false
+                let condition = self._load(args[0]);
+                if condition.is_zero() != (op == 5) {
+                    if let Some(profile) = &mut self.profile {
+                        *profile.jumps.entry(index).or_default() += 1;
+                    }
+                    let target = self._load(args[1]);
+                    self.index = match self._to_address(target.to_isize(), op_code) {
+                        Ok(address) => address,
+                        Err(error) => return self._fault(error),
+                    };
                     increment_index = false;
                 }
             }
             7 => {
                 // less than
-                let result = (self.read(args[0]) < self.read(args[1])) as isize;
-                // println!("&{} = {} < {} ? 1 : 0", args[2], self.read(args[0]), self.read(args[1]));
-                self.write(args[2], result);
+                let (a, b) = (self._load(args[0]), self._load(args[1]));
+                self._store(args[2], W::from_bool(a < b));
             }
             8 => {
                 // equals
-                let result = (self.read(args[0]) == self.read(args[1])) as isize;
-                // println!("&{} = {} == {} ? 1 : 0", args[2], self.read(args[0]), self.read(args[1]));
-                self.write(args[2], result);
+                let (a, b) = (self._load(args[0]), self._load(args[1]));
+                self._store(args[2], W::from_bool(a == b));
             }
             9 => {
                 // adjust relative base
-                // println!("rbase += {}", self.read(args[0]));
-                self.relative_base += self.read(args[0]);
+                let offset = self._load(args[0]).to_isize();
+                let relative_base =
+                    offset.and_then(|offset| self.relative_base.checked_add(offset));
+                let Some(relative_base) = relative_base else {
+                    return self._fault(overflow);
+                };
+                self.relative_base = relative_base;
             }
             99 => {
                 // exit
-                // println!("EXIT");
                 self.state = Exited;
-                return;
+                if let Some(tracer) = &mut self.tracer {
+                    tracer.halt(index, Exited);
+                }
+                return Ok(());
+            }
+            _ => {
+                // registered instruction
+                let opcodes = self.opcodes.clone().unwrap();
+                let (handler, info) = (opcodes.handler(op).unwrap(), opcodes.info(op).unwrap());
+                let mut operands = Operands {
+                    program: self,
+                    index,
+                    op_code,
+                    args,
+                    result_arg: info.result_arg,
+                    jump: None,
+                };
+                if let Err(error) = handler(&mut operands) {
+                    return self._fault(error);
+                }
+                if let Some(target) = operands.jump {
+                    self.index = target;
+                    increment_index = false;
+                }
             }
-            _ => unreachable!(),
         }
         if increment_index {
             self.index += offset;
         }
         self.state = Running;
+        Ok(())
     }
 
-    pub fn run_until<F>(programs: &mut [Program], pipes: &HashMap<usize, Vec<usize>>, condition: F)
+    // returns early, without an error, once a program runs out of budget or
+    // none of them can make progress, e.g. when they all wait for input
+    pub fn run_until<F>(
+        programs: &mut [Program<W>],
+        pipes: &HashMap<usize, Vec<usize>>,
+        condition: F,
+    ) -> Result<(), IntcodeError>
     where
-        F: Fn(&[Program]) -> bool,
+        F: Fn(&[Program<W>]) -> bool,
     {
         while !condition(programs) {
+            if programs
+                .iter()
+                .any(|program| program.state == BudgetExhausted)
+            {
+                break;
+            }
+            let num_steps: usize = programs.iter().map(|program| program.num_steps).sum();
             for index in 0..programs.len() {
-                programs[index].step();
+                programs[index].step()?;
                 let send_to_indices = pipes.get(&index);
-                if let Some(send_to_indices) = send_to_indices {
-                    for send_to_index in send_to_indices {
+                if let Some((last, others)) =
+                    send_to_indices.and_then(|indices| indices.split_last())
+                {
+                    let mut outputs = std::mem::take(&mut programs[index].outputs);
+                    for send_to_index in others {
                         programs[*send_to_index]
                             .inputs
-                            .append(&mut programs[index].outputs.clone());
+                            .extend(outputs.iter().cloned());
                     }
-                    programs[index].outputs = VecDeque::new();
+                    programs[*last].inputs.append(&mut outputs);
                 }
             }
+            if programs
+                .iter()
+                .map(|program| program.num_steps)
+                .sum::<usize>()
+                == num_steps
+            {
+                break;
+            }
         }
+        Ok(())
+    }
+}
+
+impl Program {
+    fn _parse_op_code(op_code: isize) -> (isize, Vec<isize>) {
+        let op = op_code % 100;
+        let mut modes = op_code / 100;
+        let mut param_modes: Vec<isize> = vec![];
+        while modes > 0 {
+            param_modes.push(modes % 10);
+            modes /= 10;
+        }
+        (op, param_modes)
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let mut memory: Vec<(usize, isize)> = self.memory.non_zero().collect();
+        memory.sort_unstable();
+        Snapshot {
+            memory,
+            index: self.index,
+            relative_base: self.relative_base,
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+            state: self.state,
+            fault: self.fault,
+        }
+    }
+
+    // restores the state captured by snapshot(), keeping the current tracer,
+    // input and output; the undo log starts over, since the steps it holds
+    // do not lead to the restored state
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.memory = Memory::new(self.memory.kind(), &[]);
+        for (address, value) in &snapshot.memory {
+            self.memory.write(*address, *value);
+        }
+        self.index = snapshot.index;
+        self.relative_base = snapshot.relative_base;
+        self.inputs = snapshot.inputs.clone();
+        self.outputs = snapshot.outputs.clone();
+        self.state = snapshot.state;
+        self.fault = snapshot.fault;
+        self.decoded.clear();
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+    }
+}
+
+impl Engine for Program {
+    fn send(&mut self, value: isize) {
+        Program::send(self, value)
+    }
+
+    fn receive(&mut self) -> Option<isize> {
+        Program::receive(self)
+    }
+
+    fn num_outputs(&self) -> usize {
+        Program::num_outputs(self)
+    }
+
+    fn state(&self) -> ProgramState {
+        Program::state(self)
+    }
+
+    fn step(&mut self) -> Result<(), IntcodeError> {
+        Program::step(self)
+    }
+
+    fn read(&self, address: usize) -> isize {
+        Program::read(self, address)
+    }
+
+    fn write(&mut self, address: usize, value: isize) {
+        Program::write(self, address, value)
     }
 }
@@ -0,0 +1,120 @@
+use super::{IntcodeError, Program, ProgramState, ProgramState::*};
+
+// Line-oriented front end for programs that talk ASCII. Output values above
+// 127 are not text but a final answer, kept for final_value(). With echo on,
+// the conversation is printed to stdout as it happens; ANSI rendering then
+// moves the cursor back home whenever the program starts a new frame, which
+// it marks with an empty line.
+pub struct AsciiConsole {
+    program: Program,
+    echo: bool,
+    ansi: bool,
+    next_home: bool,
+    last_char: u8,
+    final_value: Option<isize>,
+}
+
+const ANSI_HOME: &str = "\x1b[1;1H"; // jump to top left corner
+const ANSI_CLEAR: &str = "\x1b[2J";
+
+impl AsciiConsole {
+    pub fn new(program: Program) -> Self {
+        Self {
+            program,
+            echo: false,
+            ansi: false,
+            next_home: true,
+            last_char: 0,
+            final_value: None,
+        }
+    }
+
+    pub fn set_echo(&mut self, echo: bool) {
+        self.echo = echo;
+    }
+
+    pub fn set_ansi(&mut self, ansi: bool) {
+        self.ansi = ansi;
+    }
+
+    pub fn program(&mut self) -> &mut Program {
+        &mut self.program
+    }
+
+    pub fn into_program(self) -> Program {
+        self.program
+    }
+
+    pub fn state(&self) -> ProgramState {
+        self.program.state()
+    }
+
+    // the last non-ASCII value the program printed
+    pub fn final_value(&self) -> Option<isize> {
+        self.final_value
+    }
+
+    pub fn clear_screen(&mut self) {
+        if self.echo && self.ansi {
+            print!("{}{}", ANSI_CLEAR, ANSI_HOME);
+            self.next_home = false;
+        }
+    }
+
+    pub fn send_line(&mut self, line: &str) {
+        if self.echo {
+            println!("{}", line);
+        }
+        for b in line.bytes() {
+            self.program.send(b as isize);
+        }
+        self.program.send(b'\n' as isize);
+    }
+
+    // runs until the program asks for input or halts, returning its text
+    pub fn read_until_prompt(&mut self) -> Result<String, IntcodeError> {
+        let mut text = vec![];
+        loop {
+            self.program.step()?;
+            while let Some(value) = self.program.receive() {
+                if let Some(c) = self._print(value) {
+                    text.push(c);
+                }
+            }
+            if self.program.state() != Running {
+                break;
+            }
+        }
+        Ok(String::from_utf8_lossy(&text).into_owned())
+    }
+
+    // for programs that need no (more) input: runs until the program exits
+    // and returns everything it printed. Stops early if the program asks for
+    // input after all, see state().
+    pub fn read_all(&mut self) -> Result<String, IntcodeError> {
+        self.read_until_prompt()
+    }
+
+    fn _print(&mut self, value: isize) -> Option<u8> {
+        if !(0..128).contains(&value) {
+            self.final_value = Some(value);
+            return None;
+        }
+        let c = value as u8;
+        if self.echo {
+            if !self.ansi {
+                print!("{}", c as char);
+            } else if self.last_char == b'\n' && c == b'\n' {
+                self.next_home = true;
+            } else {
+                if self.next_home {
+                    print!("{}", ANSI_HOME);
+                    self.next_home = false;
+                }
+                print!("{}", c as char);
+            }
+        }
+        self.last_char = c;
+        Some(c)
+    }
+}
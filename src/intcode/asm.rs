@@ -0,0 +1,754 @@
+use super::opcodes::OpcodeRegistry;
+use hashbrown::HashMap;
+use std::fmt;
+
+// Syntax, one statement per line (';' starts a comment):
+//
+//   loop: ADD [rb+1], #5 -> [counter]   ; positional, immediate, relative
+//         JNZ [counter], #loop
+//         HLT
+//   counter: .data 0, 'A', "text\n"     ; raw words, chars and strings
+//   .const SIZE = 2 * (end - start)     ; named constant expression
+//   .macro PUSH value                   ; textual macro, \@ is unique per call
+//         ADD #value, #0 -> [rb+0]
+//         ARB #1
+//   .endm
+//
+// Expressions support numbers, labels, constants, '$' (the address of the
+// current statement), chars, parentheses and the + - * / % operators.
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+fn error<T>(line: usize, message: impl Into<String>) -> Result<T, AsmError> {
+    Err(AsmError {
+        line,
+        message: message.into(),
+    })
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Number(isize),
+    Symbol(String),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+}
+
+enum Symbol {
+    Address(usize),
+    Const(Expr, usize),
+}
+
+enum Item {
+    Instruction(isize, Vec<(isize, Expr)>),
+    Data(Vec<Expr>),
+}
+
+struct ExprParser<'a> {
+    chars: Vec<char>,
+    position: usize,
+    here: usize,
+    line: usize,
+    source: &'a str,
+}
+
+impl<'a> ExprParser<'a> {
+    fn parse(source: &'a str, here: usize, line: usize) -> Result<Expr, AsmError> {
+        let mut parser = Self {
+            chars: source.chars().collect(),
+            position: 0,
+            here,
+            line,
+            source,
+        };
+        let expr = parser.expr()?;
+        parser.skip_spaces();
+        if parser.position < parser.chars.len() {
+            return parser.fail();
+        }
+        Ok(expr)
+    }
+
+    fn fail<T>(&self) -> Result<T, AsmError> {
+        error(self.line, format!("invalid expression '{}'", self.source))
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.position < self.chars.len() && self.chars[self.position].is_whitespace() {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_spaces();
+        self.chars.get(self.position).copied()
+    }
+
+    fn expr(&mut self) -> Result<Expr, AsmError> {
+        let mut expr = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.position += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr, AsmError> {
+        let mut expr = self.unary()?;
+        while let Some(op @ ('*' | '/' | '%')) = self.peek() {
+            self.position += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, AsmError> {
+        match self.peek() {
+            Some('-') => {
+                self.position += 1;
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            Some('+') => {
+                self.position += 1;
+                self.unary()
+            }
+            _ => self.atom(),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, AsmError> {
+        let start = self.position;
+        match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                let expr = self.expr()?;
+                if self.peek() != Some(')') {
+                    return self.fail();
+                }
+                self.position += 1;
+                Ok(expr)
+            }
+            Some('$') => {
+                self.position += 1;
+                Ok(Expr::Number(self.here as isize))
+            }
+            Some('\'') => {
+                let rest: String = self.chars[self.position..].iter().collect();
+                let Some((c, length)) = parse_char(&rest[1..], '\'') else {
+                    return self.fail();
+                };
+                if self.chars.get(self.position + 1 + length) != Some(&'\'') {
+                    return self.fail();
+                }
+                self.position += length + 2;
+                Ok(Expr::Number(c as isize))
+            }
+            Some(c) if c.is_ascii_digit() => {
+                while self.position < self.chars.len() && self.chars[self.position].is_ascii_digit()
+                {
+                    self.position += 1;
+                }
+                let digits: String = self.chars[start..self.position].iter().collect();
+                let digits = digits.trim();
+                match digits.parse() {
+                    Ok(value) => Ok(Expr::Number(value)),
+                    Err(_) => self.fail(),
+                }
+            }
+            Some(c) if is_identifier_start(c) => {
+                let start = self.position;
+                while self.position < self.chars.len()
+                    && is_identifier_char(self.chars[self.position])
+                {
+                    self.position += 1;
+                }
+                Ok(Expr::Symbol(
+                    self.chars[start..self.position].iter().collect(),
+                ))
+            }
+            _ => self.fail(),
+        }
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if is_identifier_start(c)) && chars.all(is_identifier_char)
+}
+
+// parses one (possibly escaped) char, returning it with the number of source
+// chars it used
+fn parse_char(s: &str, quote: char) -> Option<(char, usize)> {
+    let mut chars = s.chars();
+    match chars.next()? {
+        '\\' => {
+            let c = match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                '0' => '\0',
+                c @ ('\\' | '\'' | '"') => c,
+                _ => return None,
+            };
+            Some((c, 2))
+        }
+        c if c == quote => None,
+        c => Some((c, 1)),
+    }
+}
+
+fn parse_string(s: &str) -> Option<Vec<char>> {
+    let mut s = s.strip_prefix('"')?.strip_suffix('"')?;
+    let mut chars = vec![];
+    while !s.is_empty() {
+        let (c, length) = parse_char(s, '"')?;
+        chars.push(c);
+        s = &s[s.char_indices().nth(length).map_or(s.len(), |(i, _)| i)..];
+    }
+    Some(chars)
+}
+
+// strips comments, ignoring ';' inside quotes
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, ';') => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+// splits on commas that are not inside brackets, parentheses or quotes
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = s[start..].trim();
+    if !last.is_empty() || !parts.is_empty() {
+        parts.push(last);
+    }
+    parts
+}
+
+// replaces whole identifiers according to the given substitutions
+fn substitute(line: &str, substitutions: &HashMap<&str, &str>) -> String {
+    let mut output = String::new();
+    let mut identifier = String::new();
+    for c in line.chars().chain(std::iter::once('\n')) {
+        if is_identifier_char(c) {
+            identifier.push(c);
+            continue;
+        }
+        if !identifier.is_empty() {
+            output += substitutions
+                .get(identifier.as_str())
+                .unwrap_or(&identifier.as_str());
+            identifier.clear();
+        }
+        output.push(c);
+    }
+    output.pop();
+    output
+}
+
+struct Macro {
+    params: Vec<String>,
+    body: Vec<String>,
+}
+
+fn expand_macros(source: &str) -> Result<Vec<(usize, String)>, AsmError> {
+    let mut macros: HashMap<String, Macro> = HashMap::new();
+    let mut lines = vec![];
+    let mut current: Option<(String, Macro)> = None;
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let code = strip_comment(line).trim();
+        let (first, rest) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+        match first.to_lowercase().as_str() {
+            ".macro" => {
+                if current.is_some() {
+                    return error(line_number, "nested macro definition");
+                }
+                let (name, params) = rest
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((rest.trim(), ""));
+                if !is_identifier(name) {
+                    return error(line_number, format!("invalid macro name '{}'", name));
+                }
+                let params: Vec<String> = split_top_level(params)
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect();
+                if !params.iter().all(|param| is_identifier(param)) {
+                    return error(line_number, "invalid macro parameters");
+                }
+                current = Some((
+                    name.to_string(),
+                    Macro {
+                        params,
+                        body: vec![],
+                    },
+                ));
+            }
+            ".endm" => match current.take() {
+                Some((name, definition)) => {
+                    macros.insert(name, definition);
+                }
+                None => return error(line_number, ".endm without .macro"),
+            },
+            _ => match &mut current {
+                Some((_, definition)) => definition.body.push(code.to_string()),
+                None => lines.push((line_number, code.to_string())),
+            },
+        }
+    }
+    if current.is_some() {
+        return error(source.lines().count(), "missing .endm");
+    }
+    let mut expanded = vec![];
+    let mut num_expansions = 0;
+    let mut stack: Vec<(usize, String, usize)> =
+        lines.into_iter().rev().map(|(n, l)| (n, l, 0)).collect();
+    while let Some((line_number, line, depth)) = stack.pop() {
+        let (label, code) = split_label(&line);
+        let (first, rest) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+        let Some(definition) = macros.get(first) else {
+            expanded.push((line_number, line));
+            continue;
+        };
+        if depth >= 64 {
+            return error(line_number, format!("macro '{}' expands too deeply", first));
+        }
+        let args = split_top_level(rest);
+        if args.len() != definition.params.len() {
+            return error(
+                line_number,
+                format!(
+                    "macro '{}' takes {} arguments",
+                    first,
+                    definition.params.len()
+                ),
+            );
+        }
+        num_expansions += 1;
+        let unique = format!("_{}", num_expansions);
+        let substitutions: HashMap<&str, &str> = definition
+            .params
+            .iter()
+            .map(|param| param.as_str())
+            .zip(args)
+            .collect();
+        for body_line in definition.body.iter().rev() {
+            let body_line = substitute(body_line, &substitutions).replace("\\@", &unique);
+            stack.push((line_number, body_line, depth + 1));
+        }
+        if let Some(label) = label {
+            stack.push((line_number, format!("{}:", label), depth));
+        }
+    }
+    Ok(expanded)
+}
+
+fn split_label(line: &str) -> (Option<&str>, &str) {
+    match line.split_once(':') {
+        Some((label, rest)) if is_identifier(label.trim()) => (Some(label.trim()), rest.trim()),
+        _ => (None, line.trim()),
+    }
+}
+
+fn parse_operand(operand: &str, here: usize, line: usize) -> Result<(isize, Expr), AsmError> {
+    if let Some(value) = operand.strip_prefix('#') {
+        return Ok((1, ExprParser::parse(value, here, line)?));
+    }
+    if let Some(inner) = operand.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        let inner = inner.trim();
+        if let Some(offset) = inner.strip_prefix("rb") {
+            if !offset.starts_with(is_identifier_char) {
+                return Ok((2, ExprParser::parse(&format!("0{}", offset), here, line)?));
+            }
+        }
+        return Ok((0, ExprParser::parse(inner, here, line)?));
+    }
+    error(line, format!("invalid operand '{}'", operand))
+}
+
+fn op_by_mnemonic(mnemonic: &str, opcodes: &OpcodeRegistry) -> Option<isize> {
+    (1..100).find(|op| {
+        opcodes
+            .info(*op)
+            .is_some_and(|info| info.mnemonic.eq_ignore_ascii_case(mnemonic))
+    })
+}
+
+fn parse_instruction(
+    mnemonic: &str,
+    operands: &str,
+    here: usize,
+    line: usize,
+    opcodes: &OpcodeRegistry,
+) -> Result<Item, AsmError> {
+    let Some(op) = op_by_mnemonic(mnemonic, opcodes) else {
+        return error(line, format!("unknown mnemonic '{}'", mnemonic));
+    };
+    let info = opcodes.info(op).unwrap();
+    let (inputs, result) = match operands.split_once("->") {
+        Some((inputs, result)) => (inputs, Some(result.trim())),
+        None => (operands, None),
+    };
+    let mut args: Vec<(isize, Expr)> = split_top_level(inputs)
+        .into_iter()
+        .map(|operand| parse_operand(operand, here, line))
+        .collect::<Result<_, _>>()?;
+    if let Some(result) = result {
+        match info.result_arg {
+            Some(result_arg) if args.len() + 1 == info.num_args => {
+                args.insert(result_arg, parse_operand(result, here, line)?);
+            }
+            Some(_) => {
+                return error(
+                    line,
+                    format!("{} takes {} operands", info.mnemonic, info.num_args),
+                )
+            }
+            None => return error(line, format!("{} has no result operand", info.mnemonic)),
+        }
+    }
+    if args.len() != info.num_args {
+        return error(
+            line,
+            format!("{} takes {} operands", info.mnemonic, info.num_args),
+        );
+    }
+    if let Some(result_arg) = info.result_arg {
+        if args[result_arg].0 == 1 {
+            return error(
+                line,
+                format!("{} cannot write to an immediate operand", info.mnemonic),
+            );
+        }
+    }
+    Ok(Item::Instruction(op, args))
+}
+
+fn parse_data(operands: &str, here: usize, line: usize) -> Result<Vec<Expr>, AsmError> {
+    let mut words = vec![];
+    for operand in split_top_level(operands) {
+        if operand.starts_with('"') {
+            match parse_string(operand) {
+                Some(chars) => words.extend(chars.into_iter().map(|c| Expr::Number(c as isize))),
+                None => return error(line, format!("invalid string {}", operand)),
+            }
+        } else {
+            words.push(ExprParser::parse(operand, here + words.len(), line)?);
+        }
+    }
+    Ok(words)
+}
+
+// constants holds the value of each constant once computed, and None for the
+// constants being computed, which would be circular to reach again
+fn eval(
+    expr: &Expr,
+    symbols: &HashMap<String, Symbol>,
+    constants: &mut HashMap<String, Option<isize>>,
+    line: usize,
+) -> Result<isize, AsmError> {
+    let overflow = || AsmError {
+        line,
+        message: "arithmetic overflow".to_string(),
+    };
+    match expr {
+        Expr::Number(value) => Ok(*value),
+        Expr::Symbol(name) => match symbols.get(name) {
+            Some(Symbol::Address(address)) => Ok(*address as isize),
+            Some(Symbol::Const(expr, const_line)) => match constants.get(name) {
+                Some(Some(value)) => Ok(*value),
+                Some(None) => error(line, "constant definitions are circular"),
+                None => {
+                    constants.insert(name.clone(), None);
+                    let value = eval(expr, symbols, constants, *const_line)?;
+                    constants.insert(name.clone(), Some(value));
+                    Ok(value)
+                }
+            },
+            None => error(line, format!("undefined symbol '{}'", name)),
+        },
+        Expr::Neg(expr) => eval(expr, symbols, constants, line)?
+            .checked_neg()
+            .ok_or_else(overflow),
+        Expr::Binary(op, left, right) => {
+            let left = eval(left, symbols, constants, line)?;
+            let right = eval(right, symbols, constants, line)?;
+            match op {
+                '+' => left.checked_add(right),
+                '-' => left.checked_sub(right),
+                '*' => left.checked_mul(right),
+                '/' => left.checked_div(right),
+                '%' => left.checked_rem(right),
+                _ => unreachable!(),
+            }
+            .ok_or_else(overflow)
+        }
+    }
+}
+
+fn define(
+    symbols: &mut HashMap<String, Symbol>,
+    name: &str,
+    symbol: Symbol,
+    line: usize,
+) -> Result<(), AsmError> {
+    if symbols.insert(name.to_string(), symbol).is_some() {
+        return error(line, format!("symbol '{}' is defined twice", name));
+    }
+    Ok(())
+}
+
+pub fn assemble(source: &str) -> Result<Vec<isize>, AsmError> {
+    assemble_with(source, &OpcodeRegistry::new())
+}
+
+// also accepts the mnemonics of the instructions registered in opcodes
+pub fn assemble_with(source: &str, opcodes: &OpcodeRegistry) -> Result<Vec<isize>, AsmError> {
+    let mut symbols: HashMap<String, Symbol> = HashMap::new();
+    let mut items: Vec<(usize, Item)> = vec![];
+    let mut address = 0;
+    for (line, code) in expand_macros(source)? {
+        let (label, code) = split_label(&code);
+        if let Some(label) = label {
+            define(&mut symbols, label, Symbol::Address(address), line)?;
+        }
+        if code.is_empty() {
+            continue;
+        }
+        let (first, rest) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+        let item = match first.to_lowercase().as_str() {
+            ".data" | "data" => Item::Data(parse_data(rest, address, line)?),
+            ".const" => {
+                let Some((name, value)) = rest.split_once('=') else {
+                    return error(line, "expected .const NAME = value");
+                };
+                let name = name.trim();
+                if !is_identifier(name) {
+                    return error(line, format!("invalid constant name '{}'", name));
+                }
+                let value = ExprParser::parse(value, address, line)?;
+                define(&mut symbols, name, Symbol::Const(value, line), line)?;
+                continue;
+            }
+            directive if directive.starts_with('.') => {
+                return error(line, format!("unknown directive '{}'", first));
+            }
+            _ => parse_instruction(first, rest, address, line, opcodes)?,
+        };
+        address += match &item {
+            Item::Instruction(_, args) => 1 + args.len(),
+            Item::Data(words) => words.len(),
+        };
+        items.push((line, item));
+    }
+    let mut constants = HashMap::new();
+    let mut intcode = vec![];
+    for (line, item) in items {
+        match item {
+            Item::Instruction(op, args) => {
+                let op_code = args
+                    .iter()
+                    .enumerate()
+                    .map(|(arg_index, (mode, _))| mode * 10isize.pow(2 + arg_index as u32))
+                    .sum::<isize>()
+                    + op;
+                intcode.push(op_code);
+                for (_, expr) in args {
+                    intcode.push(eval(&expr, &symbols, &mut constants, line)?);
+                }
+            }
+            Item::Data(words) => {
+                for expr in words {
+                    intcode.push(eval(&expr, &symbols, &mut constants, line)?);
+                }
+            }
+        }
+    }
+    Ok(intcode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_at(source: &str) -> (usize, String) {
+        let error = assemble(source).unwrap_err();
+        (error.line, error.message)
+    }
+
+    #[test]
+    fn operand_modes() {
+        assert_eq!(assemble("ADD [3], #5 -> [7]").unwrap(), [1001, 3, 5, 7]);
+        assert_eq!(
+            assemble("ADD [rb+1], [rb-2] -> [rb]").unwrap(),
+            [22201, 1, -2, 0]
+        );
+        assert_eq!(assemble("OUT #-4\nHLT").unwrap(), [104, -4, 99]);
+        assert_eq!(
+            assemble("IN -> [rb + 2 * 3]\nARB #'A'").unwrap(),
+            [203, 6, 109, 65]
+        );
+    }
+
+    #[test]
+    fn labels_and_forward_references() {
+        let source = "
+            start: JNZ #1, #end   ; jumps over the output
+                   OUT [value]
+            end:   JZ #0, #start
+            value: .data $, end - start
+        ";
+        assert_eq!(
+            assemble(source).unwrap(),
+            [1105, 1, 5, 4, 8, 1106, 0, 0, 8, 5]
+        );
+        assert_eq!(
+            error_at("JZ #0, #nowhere"),
+            (1, "undefined symbol 'nowhere'".to_string())
+        );
+        assert_eq!(
+            error_at("a: HLT\na: HLT"),
+            (2, "symbol 'a' is defined twice".to_string())
+        );
+    }
+
+    #[test]
+    fn macros() {
+        let source = "
+            .macro SKIP_IF_ZERO value
+                JZ value, #skip\\@
+                OUT value
+            skip\\@:
+            .endm
+            SKIP_IF_ZERO #0
+            SKIP_IF_ZERO [rb+1]
+            HLT
+        ";
+        assert_eq!(
+            assemble(source).unwrap(),
+            [1106, 0, 5, 104, 0, 1206, 1, 10, 204, 1, 99]
+        );
+        assert_eq!(
+            error_at(".macro TWICE a, b\nOUT a\nOUT b\n.endm\nTWICE #1"),
+            (5, "macro 'TWICE' takes 2 arguments".to_string())
+        );
+        assert_eq!(
+            error_at(".macro LOOP\nLOOP\n.endm\nLOOP"),
+            (4, "macro 'LOOP' expands too deeply".to_string())
+        );
+        assert_eq!(
+            error_at("HLT\n.macro M\nHLT"),
+            (3, "missing .endm".to_string())
+        );
+    }
+
+    #[test]
+    fn constants() {
+        let source = "
+            .const SIZE = 2 * (end - start)
+            start: .data 1, 2
+            end:   .data SIZE, 'a', \"b;\\n\"
+        ";
+        assert_eq!(assemble(source).unwrap(), [1, 2, 4, 97, 98, 59, 10]);
+        let (_, message) = error_at(".const A = B + 1\n.const B = A\n.data A");
+        assert_eq!(message, "constant definitions are circular");
+        let (_, message) = error_at(".const A = A\nOUT #A");
+        assert_eq!(message, "constant definitions are circular");
+    }
+
+    #[test]
+    fn long_constant_chains() {
+        // each constant refers to the previous one twice, which must not
+        // evaluate it twice
+        let mut source = ".const C0 = 1\n".to_string();
+        for i in 1..=40 {
+            source += &format!(".const C{} = C{} + C{}\n", i, i - 1, i - 1);
+        }
+        assert_eq!(assemble(&(source + ".data C40")).unwrap(), [1 << 40]);
+        // deep, but not circular
+        let mut source = ".const C0 = 0\n".to_string();
+        for i in 1..=70 {
+            source += &format!(".const C{} = C{} + 1\n", i, i - 1);
+        }
+        assert_eq!(assemble(&(source + ".data C70")).unwrap(), [70]);
+    }
+
+    #[test]
+    fn errors_carry_line_numbers() {
+        assert_eq!(
+            error_at("HLT\n\nFOO #1"),
+            (3, "unknown mnemonic 'FOO'".to_string())
+        );
+        assert_eq!(
+            error_at("HLT\nADD #1, #2 -> #3"),
+            (2, "ADD cannot write to an immediate operand".to_string())
+        );
+        assert_eq!(
+            error_at("OUT #1\nOUT #1, #2"),
+            (2, "OUT takes 1 operands".to_string())
+        );
+        assert_eq!(
+            error_at("HLT\nOUT (1 + 2"),
+            (2, "invalid operand '(1 + 2'".to_string())
+        );
+        assert_eq!(
+            error_at("OUT #(1 + 2"),
+            (1, "invalid expression '(1 + 2'".to_string())
+        );
+        assert_eq!(
+            assemble("HLT\n.bogus").unwrap_err().to_string(),
+            "line 2: unknown directive '.bogus'"
+        );
+    }
+}
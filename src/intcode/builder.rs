@@ -0,0 +1,82 @@
+use super::memory::MemoryKind;
+use super::opcodes::{OpcodeRegistry, Operands};
+use super::syscall::Syscalls;
+use super::word::{OverflowPolicy, Word};
+use super::{IntcodeError, OpInfo, Program};
+use std::sync::Arc;
+
+// Sets up programs with options that have to be chosen before they run:
+//
+//     let program = Program::builder(&intcode)
+//         .opcode(10, OpInfo { mnemonic: "AND", num_args: 3, result_arg: Some(2) }, |ops| {
+//             let value = ops.load(0) & ops.load(1);
+//             ops.store(value);
+//             Ok(())
+//         })
+//         .build();
+//
+// The builder can build any number of programs, which share the registry.
+#[derive(Clone)]
+pub struct ProgramBuilder<W: Word = isize> {
+    intcode: Vec<W>,
+    memory_kind: MemoryKind,
+    opcodes: Arc<OpcodeRegistry<W>>,
+    overflow: OverflowPolicy,
+}
+
+impl<W: Word> ProgramBuilder<W> {
+    pub(super) fn new(intcode: &[W]) -> Self {
+        Self {
+            intcode: intcode.to_vec(),
+            memory_kind: MemoryKind::Dense,
+            opcodes: Arc::default(),
+            overflow: OverflowPolicy::Trap,
+        }
+    }
+
+    pub fn memory(mut self, memory_kind: MemoryKind) -> Self {
+        self.memory_kind = memory_kind;
+        self
+    }
+
+    pub fn overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    // adds an instruction, see OpcodeRegistry::register()
+    pub fn opcode<F>(mut self, op: isize, info: OpInfo, handler: F) -> Self
+    where
+        F: Fn(&mut Operands<W>) -> Result<(), IntcodeError> + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.opcodes).register(op, info, handler);
+        self
+    }
+
+    // adds all the instructions of a registry, which must not clash with
+    // those added so far
+    pub fn opcodes(mut self, opcodes: &OpcodeRegistry<W>) -> Self {
+        if self.opcodes.is_empty() {
+            self.opcodes = Arc::new(opcodes.clone());
+        } else {
+            Arc::make_mut(&mut self.opcodes).extend(opcodes);
+        }
+        self
+    }
+
+    pub fn build(&self) -> Program<W> {
+        let mut program = Program::with_memory(&self.intcode, self.memory_kind);
+        program.overflow = self.overflow;
+        if !self.opcodes.is_empty() {
+            program.opcodes = Some(self.opcodes.clone());
+        }
+        program
+    }
+}
+
+impl ProgramBuilder {
+    // adds the SYS instruction, calling these host functions
+    pub fn syscalls(self, syscalls: &Syscalls) -> Self {
+        self.opcodes(&syscalls.opcodes())
+    }
+}
@@ -0,0 +1,147 @@
+use super::packet::{Capture, Packet};
+use super::varint;
+use regex::Regex;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+// Packet captures are stored either in a compact binary format or as
+// newline-delimited JSON, one packet per line:
+//
+//     {"round":12,"source":3,"destination":255,"payload":[1524,-9]}
+//
+// Binary captures start with MAGIC, followed by one record per packet: round,
+// source, destination, payload length and payload words, all varints.
+const MAGIC: &[u8] = b"ICPCAP\x01";
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub fn write_binary<W: Write>(captures: &[Capture], mut writer: W) -> io::Result<()> {
+    let mut buffer = MAGIC.to_vec();
+    for Capture { round, packet } in captures {
+        let header = [
+            *round as isize,
+            packet.source,
+            packet.destination,
+            packet.payload.len() as isize,
+        ];
+        for value in header.iter().chain(&packet.payload) {
+            varint::encode(*value, &mut buffer);
+        }
+    }
+    writer.write_all(&buffer)?;
+    writer.flush()
+}
+
+pub fn read_binary<R: Read>(reader: R) -> io::Result<Vec<Capture>> {
+    let mut reader = BufReader::new(reader);
+    let mut magic = [0; MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(invalid_data("not an Intcode packet capture"));
+    }
+    let mut captures = vec![];
+    while !reader.fill_buf()?.is_empty() {
+        let round = varint::decode(&mut reader)?;
+        let source = varint::decode(&mut reader)?;
+        let destination = varint::decode(&mut reader)?;
+        let payload_len = varint::decode(&mut reader)?;
+        if round < 0 || payload_len < 0 {
+            return Err(invalid_data("invalid packet record"));
+        }
+        let payload = (0..payload_len)
+            .map(|_| varint::decode(&mut reader))
+            .collect::<io::Result<_>>()?;
+        captures.push(Capture {
+            round: round as usize,
+            packet: Packet {
+                source,
+                destination,
+                payload,
+            },
+        });
+    }
+    Ok(captures)
+}
+
+pub fn write_ndjson<W: Write>(captures: &[Capture], mut writer: W) -> io::Result<()> {
+    for Capture { round, packet } in captures {
+        let payload: Vec<String> = packet.payload.iter().map(isize::to_string).collect();
+        writeln!(
+            writer,
+            r#"{{"round":{},"source":{},"destination":{},"payload":[{}]}}"#,
+            round,
+            packet.source,
+            packet.destination,
+            payload.join(",")
+        )?;
+    }
+    writer.flush()
+}
+
+// reads what write_ndjson() writes, in any key order and spacing
+pub fn read_ndjson<R: Read>(reader: R) -> io::Result<Vec<Capture>> {
+    let field = |name: &str| Regex::new(&format!(r#""{}"\s*:\s*(-?\d+)"#, name)).unwrap();
+    let (round, source, destination) = (field("round"), field("source"), field("destination"));
+    let payload = Regex::new(r#""payload"\s*:\s*\[([-\d,\s]*)\]"#).unwrap();
+    let mut captures = vec![];
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let number = |regex: &Regex| -> io::Result<isize> {
+            regex
+                .captures(&line)
+                .and_then(|captures| captures[1].parse().ok())
+                .ok_or_else(|| invalid_data(&format!("invalid packet record {}", line)))
+        };
+        let round = number(&round)?;
+        let packet = Packet {
+            source: number(&source)?,
+            destination: number(&destination)?,
+            payload: payload
+                .captures(&line)
+                .ok_or_else(|| invalid_data(&format!("invalid packet record {}", line)))?[1]
+                .split(',')
+                .map(str::trim)
+                .filter(|word| !word.is_empty())
+                .map(|word| word.parse().map_err(|_| invalid_data("invalid payload")))
+                .collect::<io::Result<_>>()?,
+        };
+        if round < 0 {
+            return Err(invalid_data("negative round"));
+        }
+        captures.push(Capture {
+            round: round as usize,
+            packet,
+        });
+    }
+    Ok(captures)
+}
+
+fn is_ndjson(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "ndjson" || extension == "jsonl")
+}
+
+// NDJSON for .ndjson and .jsonl files, binary otherwise
+pub fn save<P: AsRef<Path>>(captures: &[Capture], path: P) -> io::Result<()> {
+    let writer = BufWriter::new(File::create(&path)?);
+    if is_ndjson(path.as_ref()) {
+        write_ndjson(captures, writer)
+    } else {
+        write_binary(captures, writer)
+    }
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Vec<Capture>> {
+    let file = File::open(&path)?;
+    if is_ndjson(path.as_ref()) {
+        read_ndjson(file)
+    } else {
+        read_binary(file)
+    }
+}
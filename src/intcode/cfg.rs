@@ -0,0 +1,300 @@
+use super::disasm::{decode, format_instruction, format_param, label_name};
+use hashbrown::HashSet;
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// Static control-flow graph of an Intcode image, recovered without running
+// it. Code is discovered by following fall-through and immediate jump targets
+// from the entry points: address 0, any extra ones given, and the return
+// addresses of calls (see return_address()). Jumps through position or relative parameters are computed jumps and have
+// no static successors; the block ending in one is flagged instead. Code that
+// the program writes to before running it is analyzed as it is in the image.
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum EdgeKind {
+    FallThrough, // to the next instruction, without a jump
+    Taken,       // a jump with an immediate target
+    CallReturn,  // where a call returns to, via the callee's computed jump
+}
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct Edge {
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Block {
+    pub start: usize,
+    pub instructions: Vec<usize>, // addresses, in order
+    pub successors: Vec<Edge>,
+    pub computed_jump: Option<(isize, isize)>, // (param mode, param) of the target
+    pub halts: bool,
+    pub invalid: bool, // runs into words that are not a valid instruction
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Cfg {
+    pub entries: Vec<usize>,
+    pub blocks: BTreeMap<usize, Block>, // by start address
+}
+
+// static facts about the instruction at an address
+struct Flow {
+    length: usize,
+    targets: Vec<usize>,
+    falls_through: bool,
+    computed_jump: Option<(isize, isize)>,
+    halts: bool,
+    invalid: bool,
+}
+
+fn flow(intcode: &[isize], address: usize) -> Flow {
+    let mut flow = Flow {
+        length: 1,
+        targets: vec![],
+        falls_through: false,
+        computed_jump: None,
+        halts: false,
+        invalid: false,
+    };
+    let Some((op, params)) = decode(intcode, address) else {
+        flow.invalid = true;
+        return flow;
+    };
+    flow.length = 1 + params.len();
+    match op {
+        5 | 6 => {
+            let (condition, target) = (params[0], params[1]);
+            // an immediate condition makes a jump unconditional, or a no-op
+            let jumps = match condition {
+                (1, value) => (value != 0) == (op == 5),
+                _ => true,
+            };
+            flow.falls_through = !(condition.0 == 1 && jumps);
+            if jumps {
+                match target {
+                    (1, value) if value >= 0 && (value as usize) < intcode.len() => {
+                        flow.targets.push(value as usize)
+                    }
+                    (1, _) => flow.invalid = true,
+                    _ => flow.computed_jump = Some(target),
+                }
+            }
+        }
+        99 => flow.halts = true,
+        _ => flow.falls_through = true,
+    }
+    flow
+}
+
+// The return address of a call at this address, if it is one: an immediate
+// value stored through a relative parameter, right before a jump that skips
+// to the value's address otherwise. That is how compiled Intcode calls
+// functions, which then return with a computed jump.
+fn return_address(intcode: &[isize], address: usize) -> Option<usize> {
+    let (op, params) = decode(intcode, address)?;
+    let value = match (op, &params[..]) {
+        (1, [(1, a), (1, b), (2, _)]) => a.checked_add(*b)?,
+        (2, [(1, a), (1, b), (2, _)]) => a.checked_mul(*b)?,
+        _ => return None,
+    };
+    let jump = address + 1 + params.len();
+    let (op, params) = decode(intcode, jump)?;
+    let next = jump + 1 + params.len();
+    (matches!(op, 5 | 6) && value == next as isize && next < intcode.len()).then_some(next)
+}
+
+impl Cfg {
+    pub fn build(intcode: &[isize]) -> Self {
+        Cfg::build_with_entries(intcode, &[0])
+    }
+
+    pub fn build_with_entries(intcode: &[isize], entries: &[usize]) -> Self {
+        let mut leaders: HashSet<usize> = HashSet::new();
+        let mut instructions: HashSet<usize> = HashSet::new();
+        let mut pending: Vec<usize> = entries.to_vec();
+        while let Some(address) = pending.pop() {
+            leaders.insert(address);
+            let mut address = address;
+            while address < intcode.len() {
+                if !instructions.insert(address) {
+                    // joins code already seen, maybe in the middle of a block
+                    leaders.insert(address);
+                    break;
+                }
+                let flow = flow(intcode, address);
+                if let Some(next) = return_address(intcode, address) {
+                    if !leaders.contains(&next) {
+                        leaders.insert(next);
+                        pending.push(next);
+                    }
+                }
+                leaders.extend(&flow.targets);
+                pending.extend(&flow.targets);
+                if !flow.falls_through {
+                    break;
+                }
+                address += flow.length;
+                if !flow.targets.is_empty() || flow.computed_jump.is_some() {
+                    leaders.insert(address);
+                }
+            }
+        }
+
+        let mut cfg = Cfg {
+            entries: entries.to_vec(),
+            blocks: BTreeMap::new(),
+        };
+        for &start in instructions
+            .iter()
+            .filter(|address| leaders.contains(*address))
+        {
+            let mut block = Block {
+                start,
+                instructions: vec![],
+                successors: vec![],
+                computed_jump: None,
+                halts: false,
+                invalid: false,
+            };
+            let mut address = start;
+            let mut call_return = None;
+            loop {
+                block.instructions.push(address);
+                if let Some(next) = call_return.take() {
+                    // the jump that makes the call ends the block
+                    block.successors.push(Edge {
+                        to: next,
+                        kind: EdgeKind::CallReturn,
+                    });
+                }
+                call_return = return_address(intcode, address);
+                let flow = flow(intcode, address);
+                block.successors.extend(flow.targets.iter().map(|to| Edge {
+                    to: *to,
+                    kind: EdgeKind::Taken,
+                }));
+                block.computed_jump = flow.computed_jump;
+                block.halts = flow.halts;
+                block.invalid = flow.invalid;
+                let next = address + flow.length;
+                if flow.falls_through {
+                    if next >= intcode.len() {
+                        block.invalid = true;
+                    } else if leaders.contains(&next) {
+                        block.successors.push(Edge {
+                            to: next,
+                            kind: EdgeKind::FallThrough,
+                        });
+                    } else {
+                        address = next;
+                        continue;
+                    }
+                }
+                break;
+            }
+            cfg.blocks.insert(start, block);
+        }
+        cfg
+    }
+
+    // blocks ending in a jump through a position or relative parameter
+    pub fn computed_jumps(&self) -> impl Iterator<Item = &Block> {
+        self.blocks
+            .values()
+            .filter(|block| block.computed_jump.is_some())
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.blocks
+            .values()
+            .map(|block| block.successors.len())
+            .sum()
+    }
+
+    // Graphviz DOT, one box per block listing its instructions. Entry blocks
+    // are drawn bold, jumps taken solid, fall-through edges dashed and call
+    // returns dashed gray; computed jumps lead to a shared "?" node, dotted.
+    pub fn to_dot(&self, intcode: &[isize]) -> String {
+        let escape = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut dot = String::from("digraph cfg {\n    node [shape=box, fontname=monospace];\n");
+        for block in self.blocks.values() {
+            let mut label = format!("{}:\\l", label_name(block.start));
+            for &address in &block.instructions {
+                let text = match decode(intcode, address) {
+                    Some((op, params)) => {
+                        let target = match (op, params.get(1)) {
+                            (5 | 6, Some((1, target))) if *target >= 0 => {
+                                Some(label_name(*target as usize))
+                            }
+                            _ => None,
+                        };
+                        format_instruction(op, &params, target.as_deref())
+                    }
+                    None => format!("DATA {}", intcode[address]),
+                };
+                label += &format!("{:>6}: {}\\l", address, escape(&text));
+            }
+            if block.invalid {
+                label += "(invalid)\\l";
+            }
+            let style = if self.entries.contains(&block.start) {
+                ", style=bold"
+            } else {
+                ""
+            };
+            writeln!(dot, "    n{} [label=\"{}\"{}];", block.start, label, style).unwrap();
+        }
+        if self.computed_jumps().next().is_some() {
+            writeln!(dot, "    computed [label=\"?\", shape=circle];").unwrap();
+        }
+        for block in self.blocks.values() {
+            for edge in &block.successors {
+                let style = match edge.kind {
+                    EdgeKind::Taken => "",
+                    EdgeKind::FallThrough => " [style=dashed]",
+                    EdgeKind::CallReturn => " [style=dashed, color=gray]",
+                };
+                writeln!(dot, "    n{} -> n{}{};", block.start, edge.to, style).unwrap();
+            }
+            if let Some((mode, param)) = block.computed_jump {
+                writeln!(
+                    dot,
+                    "    n{} -> computed [style=dotted, label=\"{}\"];",
+                    block.start,
+                    escape(&format_param(mode, param))
+                )
+                .unwrap();
+            }
+        }
+        dot += "}\n";
+        dot
+    }
+
+    // one line per block: address, instruction count, successors and flags
+    pub fn summary(&self) -> String {
+        self.blocks
+            .values()
+            .map(|block| {
+                let mut line = format!(
+                    "{:>6}: {:>3} instructions -> {}",
+                    block.start,
+                    block.instructions.len(),
+                    block.successors.iter().map(|edge| edge.to).join(", ")
+                );
+                if let Some((mode, param)) = block.computed_jump {
+                    line += &format!(" (computed jump {})", format_param(mode, param));
+                }
+                if block.halts {
+                    line += " (halts)";
+                }
+                if block.invalid {
+                    line += " (invalid)";
+                }
+                line
+            })
+            .join("\n")
+    }
+}
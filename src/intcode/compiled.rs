@@ -0,0 +1,747 @@
+use super::snapshot::Snapshot;
+use super::word::{OverflowPolicy, Word};
+use super::{
+    op_info, Decoded, Engine, IntcodeError, IntcodeError::*, Program, ProgramState,
+    ProgramState::*, MAX_CACHED_INDEX,
+};
+use hashbrown::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Compiles each basic block of the program into a list of closures, with
+// parameter modes and immediate values already bound. Blocks end after an
+// output, a jump or an exit, and input instructions only ever start a block,
+// so step() still stops whenever the program outputs or needs input. When the
+// program writes to one of its own compiled instructions, the blocks spanning
+// that word are dropped and the word is left to the interpreter from then on,
+// as is any code beyond MAX_CACHED_INDEX.
+// Compiling costs more than interpreting an instruction once, so a block is
+// only compiled once it has run COMPILE_THRESHOLD times, and until then is
+// left to the interpreter. Programs made with spawn() share the blocks they
+// compile from unmodified code, so many short runs of the same program only
+// compile each block once.
+
+enum Flow {
+    Next,
+    Wrote(usize),
+    Jump(usize),
+    Wait,
+    Exit,
+}
+
+// a parameter with its mode already applied
+#[derive(Copy, Clone)]
+enum Operand {
+    Immediate(isize),
+    Position(usize),
+    Relative(isize),
+    Negative(isize), // a position parameter that can only fault
+}
+
+impl Operand {
+    fn new(mode: isize, param: isize) -> Self {
+        match mode {
+            0 if param < 0 => Operand::Negative(param),
+            0 => Operand::Position(param as usize),
+            1 => Operand::Immediate(param),
+            _ => Operand::Relative(param),
+        }
+    }
+
+    // immediate operands never get here, decoding rejects writes to them
+    #[inline]
+    fn address(
+        self,
+        program: &Program,
+        index: usize,
+        op_code: isize,
+    ) -> Result<usize, IntcodeError> {
+        let address = match self {
+            Operand::Position(address) => return Ok(address),
+            Operand::Relative(offset) => program
+                .relative_base
+                .checked_add(offset)
+                .ok_or(Overflow { index, op_code })?,
+            Operand::Negative(address) => address,
+            Operand::Immediate(_) => return Err(WriteToImmediate { index, op_code }),
+        };
+        if address < 0 {
+            return Err(NegativeAddress {
+                index,
+                op_code,
+                address,
+            });
+        }
+        Ok(address as usize)
+    }
+
+    #[inline]
+    fn load(self, program: &Program, index: usize, op_code: isize) -> Result<isize, IntcodeError> {
+        match self {
+            Operand::Immediate(value) => Ok(value),
+            Operand::Position(address) => Ok(program.memory.read(address)),
+            _ => Ok(program.memory.read(self.address(program, index, op_code)?)),
+        }
+    }
+}
+
+type Op = Box<dyn Fn(&mut Program) -> Result<Flow, IntcodeError> + Send + Sync>;
+
+struct Block {
+    ops: Vec<Op>,
+    addresses: Vec<usize>,
+    end: usize,
+    overflow: OverflowPolicy,
+}
+
+// the code programs are spawned from, and what they learned running it
+#[derive(Default)]
+struct Shared {
+    intcode: Vec<isize>,
+    cache: Mutex<Cache>,
+}
+
+#[derive(Default)]
+struct Cache {
+    blocks: Vec<Option<Arc<Block>>>, // compiled from unmodified code
+    runs: Vec<u8>,                   // times each block ran before being compiled
+}
+
+enum Lookup {
+    Compiled(Arc<Block>),
+    Cold,
+    Hot,
+}
+
+const MAX_BLOCK_LENGTH: usize = 64;
+const COMPILE_THRESHOLD: u8 = 8;
+
+fn compile_instruction(index: usize, decoded: &Decoded, overflow_policy: OverflowPolicy) -> Op {
+    let Decoded {
+        op,
+        op_code,
+        modes,
+        params,
+        ..
+    } = *decoded;
+    let [a, b, c] = [0, 1, 2].map(|i| Operand::new(modes[i], params[i]));
+    let overflow = Overflow { index, op_code };
+    match op {
+        1 | 2 | 7 | 8 => {
+            let compute: fn(isize, isize, OverflowPolicy) -> Option<isize> = match op {
+                1 => |a, b, overflow| a.add(&b, overflow),
+                2 => |a, b, overflow| a.mul(&b, overflow),
+                7 => |a, b, _| Some((a < b) as isize),
+                _ => |a, b, _| Some((a == b) as isize),
+            };
+            Box::new(move |program| {
+                let a = a.load(program, index, op_code)?;
+                let b = b.load(program, index, op_code)?;
+                let result = c.address(program, index, op_code)?;
+                let value = compute(a, b, overflow_policy).ok_or(overflow)?;
+                program.write(result, value);
+                Ok(Flow::Wrote(result))
+            })
+        }
+        3 => Box::new(move |program| {
+            let result = a.address(program, index, op_code)?;
+            match program._next_input() {
+                Some(input) => {
+                    program.write(result, input);
+                    Ok(Flow::Wrote(result))
+                }
+                None => Ok(Flow::Wait),
+            }
+        }),
+        4 => Box::new(move |program| {
+            let output = a.load(program, index, op_code)?;
+            program._emit(output);
+            Ok(Flow::Next)
+        }),
+        5 | 6 => {
+            let jump_if_true = op == 5;
+            Box::new(move |program| {
+                let condition = a.load(program, index, op_code)?;
+                let target = b.load(program, index, op_code)?;
+                if (condition != 0) != jump_if_true {
+                    return Ok(Flow::Next);
+                }
+                if target < 0 {
+                    return Err(NegativeAddress {
+                        index,
+                        op_code,
+                        address: target,
+                    });
+                }
+                Ok(Flow::Jump(target as usize))
+            })
+        }
+        9 => Box::new(move |program| {
+            let offset = a.load(program, index, op_code)?;
+            program.relative_base = program.relative_base.checked_add(offset).ok_or(overflow)?;
+            Ok(Flow::Next)
+        }),
+        99 => Box::new(|_| Ok(Flow::Exit)),
+        _ => unreachable!(),
+    }
+}
+
+pub struct CompiledProgram {
+    program: Program,
+    blocks: Vec<Option<Arc<Block>>>, // by start address
+    coverage: Vec<u32>,              // number of compiled blocks spanning each address
+    interpreted: HashSet<usize>,     // words the program modified itself
+    threshold: u8,
+    shared: Arc<Shared>,
+}
+
+impl CompiledProgram {
+    pub fn new(intcode: &[isize]) -> Self {
+        CompiledProgram::from_program(Program::new(intcode))
+    }
+
+    // spawn() starts from the memory the program has now, up to
+    // MAX_CACHED_INDEX
+    pub fn from_program(program: Program) -> Self {
+        let mut intcode = vec![];
+        for (address, value) in program.memory.non_zero() {
+            if address >= MAX_CACHED_INDEX {
+                continue;
+            }
+            if intcode.len() <= address {
+                intcode.resize(address + 1, 0);
+            }
+            intcode[address] = value;
+        }
+        Self {
+            program,
+            blocks: vec![],
+            coverage: vec![],
+            interpreted: HashSet::new(),
+            threshold: COMPILE_THRESHOLD,
+            shared: Arc::new(Shared {
+                intcode,
+                ..Default::default()
+            }),
+        }
+    }
+
+    // a new instance of the program this one started as, sharing the blocks
+    // either compiles
+    pub fn spawn(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            threshold: self.threshold,
+            ..CompiledProgram::from_program(Program::new(&self.shared.intcode))
+        }
+    }
+
+    pub fn into_program(self) -> Program {
+        self.program
+    }
+
+    // read-only access to the underlying interpreter state
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn read(&self, address: usize) -> isize {
+        self.program.read(address)
+    }
+
+    pub fn write(&mut self, address: usize, value: isize) {
+        self.program.write(address, value);
+        self._invalidate(address);
+    }
+
+    // see Program::set_budget()
+    pub fn set_budget(&mut self, steps: Option<usize>, timeout: Option<Duration>) {
+        self.program.set_budget(steps, timeout);
+    }
+
+    pub fn send(&mut self, value: isize) {
+        self.program.send(value);
+    }
+
+    pub fn receive(&mut self) -> Option<isize> {
+        self.program.receive()
+    }
+
+    pub fn num_outputs(&self) -> usize {
+        self.program.num_outputs()
+    }
+
+    pub fn state(&self) -> ProgramState {
+        self.program.state()
+    }
+
+    pub fn fault(&self) -> Option<IntcodeError> {
+        self.program.fault()
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        self.program.snapshot()
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.program.restore(snapshot);
+        self.blocks.clear();
+        self.coverage.clear();
+        self.interpreted.clear();
+    }
+
+    fn _compile(&mut self, index: usize) -> Option<Arc<Block>> {
+        let mut ops = vec![];
+        let mut addresses = vec![];
+        let mut address = index;
+        while ops.len() < MAX_BLOCK_LENGTH {
+            let Ok(decoded) = self.program._decode_at(address) else {
+                break;
+            };
+            let end = address + 1 + decoded.num_args;
+            if end > MAX_CACHED_INDEX
+                || (decoded.op == 3 && !ops.is_empty())
+                || (address..end).any(|address| self.interpreted.contains(&address))
+            {
+                break;
+            }
+            // code modified before it got compiled is left to the interpreter
+            // too, as blocks compiled from it could not be shared
+            let modified = (address..end).filter(|address| self._modified(*address));
+            let modified: Vec<usize> = modified.collect();
+            if !modified.is_empty() {
+                self.interpreted.extend(modified);
+                break;
+            }
+            ops.push(compile_instruction(
+                address,
+                &decoded,
+                self.program.overflow,
+            ));
+            addresses.push(address);
+            address = end;
+            if matches!(decoded.op, 4 | 5 | 6 | 99) {
+                break;
+            }
+        }
+        if ops.is_empty() {
+            return None;
+        }
+        let block = Arc::new(Block {
+            ops,
+            addresses,
+            end: address,
+            overflow: self.program.overflow,
+        });
+        if self._unmodified(index, address) {
+            let mut cache = self.shared.cache.lock().unwrap();
+            if cache.blocks.len() <= index {
+                cache.blocks.resize(index + 1, None);
+            }
+            cache.blocks[index] = Some(block.clone());
+        }
+        self._install(index, block.clone());
+        Some(block)
+    }
+
+    fn _modified(&self, address: usize) -> bool {
+        self.shared
+            .intcode
+            .get(address)
+            .is_some_and(|word| self.read(address) != *word)
+    }
+
+    // whether these words still hold the code the program was spawned from
+    fn _unmodified(&self, start: usize, end: usize) -> bool {
+        end <= self.shared.intcode.len() && !(start..end).any(|address| self._modified(address))
+    }
+
+    // finds a block another instance compiled that is valid for this one,
+    // or counts one more run of the block starting here
+    fn _lookup(&self, index: usize) -> Lookup {
+        let mut cache = self.shared.cache.lock().unwrap();
+        if let Some(Some(block)) = cache.blocks.get(index) {
+            if block.overflow == self.program.overflow
+                && self._unmodified(index, block.end)
+                && !(index..block.end).any(|address| self.interpreted.contains(&address))
+            {
+                return Lookup::Compiled(block.clone());
+            }
+        }
+        if cache.runs.len() <= index {
+            cache.runs.resize(index + 1, 0);
+        }
+        cache.runs[index] = cache.runs[index].saturating_add(1);
+        if cache.runs[index] <= self.threshold {
+            Lookup::Cold
+        } else {
+            Lookup::Hot
+        }
+    }
+
+    fn _install(&mut self, index: usize, block: Arc<Block>) {
+        if self.coverage.len() < block.end {
+            self.coverage.resize(block.end, 0);
+        }
+        if self.blocks.len() <= index {
+            self.blocks.resize(index + 1, None);
+        }
+        for covered in index..block.end {
+            self.coverage[covered] += 1;
+        }
+        self.blocks[index] = Some(block);
+    }
+
+    // drops the compiled blocks spanning this address, returning true if any
+    fn _invalidate(&mut self, address: usize) -> bool {
+        if self.coverage.get(address).is_none_or(|count| *count == 0) {
+            return false;
+        }
+        // a block holds at most MAX_BLOCK_LENGTH instructions of 4 words
+        let first = address.saturating_sub(4 * MAX_BLOCK_LENGTH);
+        for start in first..=address.min(self.blocks.len().saturating_sub(1)) {
+            if self.blocks[start]
+                .as_ref()
+                .is_some_and(|block| address < block.end)
+            {
+                let block = self.blocks[start].take().unwrap();
+                for covered in start..block.end {
+                    self.coverage[covered] -= 1;
+                }
+            }
+        }
+        true
+    }
+
+    // executes one instruction with the interpreter, which does not check
+    // whether it writes to compiled code, so find out beforehand
+    fn _interpret(&mut self) -> Result<(), IntcodeError> {
+        if self.coverage.is_empty() {
+            return self.program.step();
+        }
+        let write = self.program._fetch().ok().and_then(|decoded| {
+            let result_arg = op_info(decoded.op)?.result_arg?;
+            Some(self.program._resolve(&decoded).ok()?[result_arg])
+        });
+        let result = self.program.step();
+        if let Some(address) = write {
+            if self.program.state() == Running && self._invalidate(address) {
+                self.interpreted.insert(address);
+            }
+        }
+        result
+    }
+
+    // interprets up to where the compiled block would have ended
+    fn _interpret_block(&mut self) -> Result<(), IntcodeError> {
+        for i in 0..MAX_BLOCK_LENGTH {
+            let op = self.program.read(self.program.index) % 100;
+            if op == 3 && i > 0 {
+                break;
+            }
+            self._interpret()?;
+            if self.program.state() != Running || matches!(op, 4 | 5 | 6 | 99) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // runs the basic block starting at the current instruction
+    pub fn step(&mut self) -> Result<(), IntcodeError> {
+        let index = self.program.index;
+        if self.program.tracer.is_some()
+            || self.program.profile.is_some()
+            || self.program.history.is_some()
+            || self.program.opcodes.is_some()
+            || !matches!(self.program.state, Running | WaitingForInput)
+            || index >= MAX_CACHED_INDEX
+            || self.interpreted.contains(&index)
+        {
+            return self._interpret();
+        }
+        let block = match self.blocks.get(index).cloned().flatten() {
+            Some(block) => block,
+            None => match self._lookup(index) {
+                Lookup::Compiled(block) => {
+                    self._install(index, block.clone());
+                    block
+                }
+                Lookup::Cold => return self._interpret_block(),
+                Lookup::Hot => match self._compile(index) {
+                    Some(block) => block,
+                    None => return self._interpret(),
+                },
+            },
+        };
+        if self.program._budget_exhausted() {
+            self.program.state = BudgetExhausted;
+            return Ok(());
+        }
+        // leave it to the interpreter to stop exactly where the budget runs out
+        if self
+            .program
+            .budget
+            .is_some_and(|budget| budget < block.ops.len())
+        {
+            return self._interpret();
+        }
+        for (i, op) in block.ops.iter().enumerate() {
+            let flow = op(&mut self.program);
+            if !matches!(flow, Ok(Flow::Wait)) {
+                self.program._count_steps(1);
+            }
+            match flow {
+                Err(error) => {
+                    self.program.index = block.addresses[i];
+                    return self.program._fault(error);
+                }
+                Ok(Flow::Next) => {}
+                Ok(Flow::Wrote(address)) => {
+                    if self.coverage.get(address).is_some_and(|count| *count > 0) {
+                        self.interpreted.insert(address);
+                        self._invalidate(address);
+                        if index <= address && address < block.end {
+                            // the rest of this block may be stale
+                            self.program.index = *block.addresses.get(i + 1).unwrap_or(&block.end);
+                            self.program.state = Running;
+                            return Ok(());
+                        }
+                    }
+                }
+                Ok(Flow::Jump(target)) => {
+                    self.program.index = target;
+                    self.program.state = Running;
+                    return Ok(());
+                }
+                Ok(Flow::Wait) => {
+                    self.program.index = block.addresses[i];
+                    self.program.state = WaitingForInput;
+                    return Ok(());
+                }
+                Ok(Flow::Exit) => {
+                    self.program.index = block.addresses[i];
+                    self.program.state = Exited;
+                    return Ok(());
+                }
+            }
+        }
+        self.program.index = block.end;
+        self.program.state = Running;
+        Ok(())
+    }
+}
+
+impl Engine for CompiledProgram {
+    fn send(&mut self, value: isize) {
+        CompiledProgram::send(self, value)
+    }
+
+    fn receive(&mut self) -> Option<isize> {
+        CompiledProgram::receive(self)
+    }
+
+    fn num_outputs(&self) -> usize {
+        CompiledProgram::num_outputs(self)
+    }
+
+    fn state(&self) -> ProgramState {
+        CompiledProgram::state(self)
+    }
+
+    fn step(&mut self) -> Result<(), IntcodeError> {
+        CompiledProgram::step(self)
+    }
+
+    fn read(&self, address: usize) -> isize {
+        CompiledProgram::read(self, address)
+    }
+
+    fn write(&mut self, address: usize, value: isize) {
+        CompiledProgram::write(self, address, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Vec<isize> {
+        input
+            .trim()
+            .split(',')
+            .map(|n| n.parse().unwrap())
+            .collect()
+    }
+
+    // runs both engines side by side, catching the interpreter up with each
+    // block the compiled program runs and comparing their registers, and
+    // every so often and at the end their whole state
+    fn compare(compiled: &mut CompiledProgram, program: &mut Program) {
+        for block in 0.. {
+            let num_steps = compiled.program().num_steps();
+            let result = compiled.step();
+            let target = program.num_steps() + compiled.program().num_steps() - num_steps;
+            let mut expected = Ok(());
+            while program.state() == Running && program.num_steps() < target {
+                expected = program.step();
+            }
+            if program.state() == Running && compiled.state() != Running {
+                expected = program.step();
+            }
+            assert_eq!(result, expected);
+            let compiled_registers = (compiled.program().index(), compiled.state());
+            assert_eq!(compiled_registers, (program.index(), program.state()));
+            assert_eq!(compiled.program().relative_base(), program.relative_base());
+            assert_eq!(compiled.num_outputs(), program.num_outputs());
+            if block % 1000 == 0 || compiled.state() != Running {
+                assert_eq!(compiled.snapshot(), program.snapshot());
+            }
+            if compiled.state() != Running {
+                return;
+            }
+        }
+    }
+
+    // with blocks compiled right away and once they get hot
+    fn compare_with_inputs(intcode: &[isize], inputs: &[isize]) -> Vec<isize> {
+        let mut outputs = vec![];
+        for threshold in [0, COMPILE_THRESHOLD] {
+            let mut compiled = CompiledProgram::new(intcode);
+            compiled.threshold = threshold;
+            let mut program = Program::new(intcode);
+            for input in inputs {
+                compiled.send(*input);
+                program.send(*input);
+            }
+            compare(&mut compiled, &mut program);
+            outputs.clear();
+            while let Some(output) = compiled.receive() {
+                outputs.push(output);
+            }
+        }
+        outputs
+    }
+
+    #[test]
+    fn day_inputs() {
+        let mut day02 = parse(include_str!("../../data/day02.txt"));
+        (day02[1], day02[2]) = (12, 2);
+        compare_with_inputs(&day02, &[]);
+        let day05 = parse(include_str!("../../data/day05.txt"));
+        compare_with_inputs(&day05, &[1]);
+        compare_with_inputs(&day05, &[5]);
+        let day09 = parse(include_str!("../../data/day09.txt"));
+        compare_with_inputs(&day09, &[1]);
+        let day13 = parse(include_str!("../../data/day13.txt"));
+        compare_with_inputs(&day13, &[]);
+        let day17 = parse(include_str!("../../data/day17.txt"));
+        compare_with_inputs(&day17, &[]);
+        let day19 = parse(include_str!("../../data/day19.txt"));
+        for (x, y) in [(0, 0), (5, 7), (40, 49)] {
+            compare_with_inputs(&day19, &[x, y]);
+        }
+    }
+
+    #[test]
+    fn writes_to_the_current_block() {
+        // the first instruction changes an operand of the second one
+        let intcode = [1101, 7, 0, 6, 1101, 1, 0, 20, 4, 20, 99];
+        assert_eq!(compare_with_inputs(&intcode, &[]), [8]);
+    }
+
+    #[test]
+    fn writes_to_another_block() {
+        // outputs 0 + 0, patches the block doing that to 5 + 0 and reruns it
+        let intcode = [
+            1101, 0, 0, 30, 4, 30, 1006, 31, 12, 99, 0, 0, 1101, 5, 0, 1, 1101, 1, 0, 31, 1105, 1,
+            0,
+        ];
+        assert_eq!(compare_with_inputs(&intcode, &[]), [0, 5]);
+    }
+
+    #[test]
+    fn faults_inside_a_block() {
+        let negative_address = [1101, 1, 1, 20, 1, -1, 0, 20, 99];
+        compare_with_inputs(&negative_address, &[]);
+        let write_to_immediate = [1101, 1, 1, 20, 11101, 1, 1, 20, 99];
+        compare_with_inputs(&write_to_immediate, &[]);
+        let overflow = [1101, 1, 1, 20, 1102, isize::MAX, 2, 20, 99];
+        compare_with_inputs(&overflow, &[]);
+        let invalid_op_code = [1101, 1, 1, 20, 77, 99];
+        compare_with_inputs(&invalid_op_code, &[]);
+        let mut compiled = CompiledProgram::new(&negative_address);
+        compiled.threshold = 0;
+        assert!(compiled.step().is_err());
+        assert_eq!(compiled.program().index(), 4);
+        assert_eq!(compiled.read(20), 2);
+    }
+
+    #[test]
+    fn budgets() {
+        let day09 = parse(include_str!("../../data/day09.txt"));
+        for steps in [0, 1, 10, 63, 64, 65, 1000] {
+            let mut compiled = CompiledProgram::new(&day09);
+            compiled.threshold = 0;
+            let mut program = Program::new(&day09);
+            compiled.send(2);
+            program.send(2);
+            compiled.set_budget(Some(steps), None);
+            program.set_budget(Some(steps), None);
+            compare(&mut compiled, &mut program);
+            assert_eq!(compiled.state(), BudgetExhausted);
+            assert_eq!(compiled.program().num_steps(), steps);
+        }
+    }
+
+    #[test]
+    fn compiles_hot_blocks() {
+        let day09 = parse(include_str!("../../data/day09.txt"));
+        let mut compiled = CompiledProgram::new(&day09);
+        let mut program = Program::new(&day09);
+        compiled.send(2);
+        program.send(2);
+        compiled.set_budget(Some(100_000), None);
+        program.set_budget(Some(100_000), None);
+        compare(&mut compiled, &mut program);
+        assert!(compiled.blocks.iter().any(|block| block.is_some()));
+    }
+
+    #[test]
+    fn spawned_programs_share_blocks() {
+        let day19 = parse(include_str!("../../data/day19.txt"));
+        let drone = CompiledProgram::new(&day19);
+        for (x, y) in (0..10).flat_map(|y| (0..10).map(move |x| (x * 5, y * 5))) {
+            let mut compiled = drone.spawn();
+            let mut program = Program::new(&day19);
+            for input in [x, y] {
+                compiled.send(input);
+                program.send(input);
+            }
+            compare(&mut compiled, &mut program);
+        }
+        let cache = drone.shared.cache.lock().unwrap();
+        assert!(cache.blocks.iter().any(|block| block.is_some()));
+    }
+
+    #[test]
+    fn restore() {
+        let intcode = [
+            1101, 0, 0, 30, 4, 30, 1006, 31, 12, 99, 0, 0, 1101, 5, 0, 1, 1101, 1, 0, 31, 1105, 1,
+            0,
+        ];
+        let mut compiled = CompiledProgram::new(&intcode);
+        compiled.threshold = 0;
+        let start = compiled.snapshot();
+        while compiled.state() == Running {
+            compiled.step().unwrap();
+        }
+        // the program patched itself, which the restored one has to undo
+        compiled.restore(&start);
+        let mut program = Program::new(&intcode);
+        compare(&mut compiled, &mut program);
+        assert_eq!(compiled.receive(), Some(0));
+        assert_eq!(compiled.receive(), Some(5));
+        assert_eq!(compiled.receive(), None);
+    }
+}
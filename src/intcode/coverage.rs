@@ -0,0 +1,192 @@
+use super::disasm;
+use super::profile::Profile;
+use super::{op_info, Program};
+use hashbrown::{HashMap, HashSet};
+use itertools::Itertools;
+use std::fmt::Write;
+
+// Which words of a program were executed as code and which were accessed as
+// data, built from the Profile of one or more runs. Instruction lengths are
+// taken from the original intcode, so an instruction whose op code was
+// modified before it ran may be attributed the wrong words.
+#[derive(Clone, Debug, Default)]
+pub struct Coverage {
+    pub len: usize,                   // words in the original program
+    pub instructions: HashSet<usize>, // addresses executed as instructions
+    pub code: HashSet<usize>,         // every word of those instructions
+    pub reads: HashSet<usize>,        // memory read by the program
+    pub writes: HashSet<usize>,
+    pub branches: HashMap<usize, Branch>, // conditional jumps executed
+}
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub struct Branch {
+    pub taken: bool,
+    pub fallen_through: bool,
+}
+
+impl Branch {
+    pub fn is_covered(&self) -> bool {
+        self.taken && self.fallen_through
+    }
+}
+
+fn instruction_len(intcode: &[isize], address: usize) -> usize {
+    let op = intcode
+        .get(address)
+        .map(|op_code| Program::_parse_op_code(*op_code).0);
+    1 + op.and_then(op_info).map_or(0, |info| info.num_args)
+}
+
+impl Coverage {
+    pub fn from_profile(profile: &Profile, intcode: &[isize]) -> Self {
+        let mut coverage = Coverage {
+            len: intcode.len(),
+            reads: profile.reads.keys().copied().collect(),
+            writes: profile.writes.keys().copied().collect(),
+            ..Default::default()
+        };
+        for (&address, &count) in &profile.executions {
+            coverage.instructions.insert(address);
+            coverage
+                .code
+                .extend(address..address + instruction_len(intcode, address));
+            let op = Program::_parse_op_code(intcode.get(address).copied().unwrap_or(0)).0;
+            if op == 5 || op == 6 {
+                let taken = profile.jumps.get(&address).copied().unwrap_or(0);
+                coverage.branches.insert(
+                    address,
+                    Branch {
+                        taken: taken > 0,
+                        fallen_through: count > taken,
+                    },
+                );
+            }
+        }
+        coverage
+    }
+
+    // adds the coverage of another run of the same program
+    pub fn merge(&mut self, other: &Coverage) {
+        self.len = self.len.max(other.len);
+        self.instructions.extend(&other.instructions);
+        self.code.extend(&other.code);
+        self.reads.extend(&other.reads);
+        self.writes.extend(&other.writes);
+        for (address, branch) in &other.branches {
+            let entry = self.branches.entry(*address).or_default();
+            entry.taken |= branch.taken;
+            entry.fallen_through |= branch.fallen_through;
+        }
+    }
+
+    // words accessed by the program that are not part of an executed
+    // instruction (so immediate operands do not count)
+    pub fn data(&self) -> HashSet<usize> {
+        self.reads
+            .union(&self.writes)
+            .filter(|address| !self.code.contains(*address))
+            .copied()
+            .collect()
+    }
+
+    // the disassembly, using the coverage to tell code from data
+    pub fn disassemble(&self, intcode: &[isize]) -> Vec<disasm::Line> {
+        disasm::disassemble_with(intcode, &self.instructions, &self.data())
+    }
+
+    // the disassembly listing, each line marked with how it was covered:
+    // "run" for executed instructions (noting branches that only went one
+    // way), "-" for instructions that never ran and r/w for data accesses
+    pub fn listing(&self, intcode: &[isize]) -> String {
+        let mut report = String::new();
+        for line in self.disassemble(intcode) {
+            if let Some(label) = &line.label {
+                writeln!(report, "{:>17}{}:", "", label).unwrap();
+            }
+            let words = line.address..line.address + line.words.len();
+            let marker = if self.instructions.contains(&line.address) {
+                let modified = words.clone().any(|address| self.writes.contains(&address));
+                let marker = match self.branches.get(&line.address) {
+                    Some(branch) if !branch.taken => "run, never jumps",
+                    Some(branch) if !branch.fallen_through => "run, always jumps",
+                    _ => "run",
+                };
+                if modified {
+                    format!("{} (w)", marker)
+                } else {
+                    marker.to_string()
+                }
+            } else {
+                let read = words.clone().any(|address| self.reads.contains(&address));
+                let written = words.clone().any(|address| self.writes.contains(&address));
+                match (read, written, line.text.starts_with("DATA")) {
+                    (true, true, _) => "rw".to_string(),
+                    (true, false, _) => "r".to_string(),
+                    (false, true, _) => "w".to_string(),
+                    (false, false, false) => "-".to_string(),
+                    (false, false, true) => String::new(),
+                }
+            };
+            writeln!(
+                report,
+                "{:<17} {:>6}: {:<24} {}",
+                marker,
+                line.address,
+                line.words.iter().join(","),
+                line.text
+            )
+            .unwrap();
+        }
+        report
+    }
+
+    // A one-line JSON object with the totals, for scripts:
+    //
+    //     {"words":1024,"instructions":310,"executed":207,...}
+    //
+    // Instructions are counted on the disassembly, memory beyond the program
+    // is counted separately as extra_memory, and partial_branches lists the
+    // conditional jumps that only ever went one way.
+    pub fn summary(&self, intcode: &[isize]) -> String {
+        let lines = self.disassemble(intcode);
+        let instructions = lines
+            .iter()
+            .filter(|line| !line.text.starts_with("DATA"))
+            .count();
+        let in_program = |addresses: &HashSet<usize>| {
+            addresses
+                .iter()
+                .filter(|address| **address < self.len)
+                .count()
+        };
+        let data = self.data();
+        let touched: HashSet<usize> = self.code.union(&data).copied().collect();
+        let partial_branches: Vec<usize> = self
+            .branches
+            .iter()
+            .filter(|(_, branch)| !branch.is_covered())
+            .map(|(address, _)| *address)
+            .sorted()
+            .collect();
+        format!(
+            concat!(
+                r#"{{"words":{},"instructions":{},"executed":{},"code_words":{},"#,
+                r#""data_words":{},"untouched_words":{},"modified_code_words":{},"#,
+                r#""extra_memory":{},"branches":{},"covered_branches":{},"#,
+                r#""partial_branches":[{}]}}"#
+            ),
+            self.len,
+            instructions,
+            self.instructions.len(),
+            in_program(&self.code),
+            in_program(&data),
+            self.len - in_program(&touched),
+            in_program(&self.code.intersection(&self.writes).copied().collect()),
+            touched.len() - in_program(&touched),
+            self.branches.len(),
+            self.branches.len() - partial_branches.len(),
+            partial_branches.iter().join(",")
+        )
+    }
+}
@@ -0,0 +1,167 @@
+use super::{op_info, Program};
+use hashbrown::HashSet;
+use itertools::Itertools;
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Line {
+    pub address: usize,
+    pub words: Vec<isize>,
+    pub label: Option<String>,
+    pub text: String,
+}
+
+// (op, [(param mode, param)]), or None if the words at this address cannot be
+// executed as an instruction
+type Decoded = Option<(isize, Vec<(isize, isize)>)>;
+
+pub fn label_name(address: usize) -> String {
+    format!("L{}", address)
+}
+
+pub fn format_param(mode: isize, param: isize) -> String {
+    match mode {
+        0 => format!("[{}]", param),
+        1 => format!("#{}", param),
+        2 if param < 0 => format!("[rb{}]", param),
+        2 => format!("[rb+{}]", param),
+        _ => format!("?{}", param),
+    }
+}
+
+pub fn decode(intcode: &[isize], address: usize) -> Decoded {
+    let (op, param_modes) = Program::_parse_op_code(*intcode.get(address)?);
+    let info = op_info(op)?;
+    if address + info.num_args >= intcode.len() {
+        return None;
+    }
+    (0..info.num_args)
+        .map(|arg_index| {
+            let mode = *param_modes.get(arg_index).unwrap_or(&0);
+            let valid = match mode {
+                0 | 2 => true,
+                1 => info.result_arg != Some(arg_index),
+                _ => false,
+            };
+            valid.then(|| (mode, intcode[address + arg_index + 1]))
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|params| (op, params))
+}
+
+fn jump_target(intcode: &[isize], decoded: &Decoded) -> Option<usize> {
+    match decoded {
+        Some((5 | 6, params)) if params[1].0 == 1 => {
+            let target = params[1].1;
+            (target >= 0 && (target as usize) < intcode.len()).then_some(target as usize)
+        }
+        _ => None,
+    }
+}
+
+// linear sweep, refusing to decode instructions that would swallow a label
+// (or any other boundary), and decoding known data words as data
+fn sweep(
+    intcode: &[isize],
+    boundaries: &HashSet<usize>,
+    data: &HashSet<usize>,
+) -> Vec<(usize, Decoded)> {
+    let mut address = 0;
+    let mut decoded_lines = vec![];
+    while address < intcode.len() {
+        let decoded = decode(intcode, address).filter(|(op, _)| {
+            let length = 1 + op_info(*op).unwrap().num_args;
+            !data.contains(&address)
+                && !(1..length).any(|offset| boundaries.contains(&(address + offset)))
+        });
+        let length = match &decoded {
+            Some((_, params)) => 1 + params.len(),
+            None => 1,
+        };
+        decoded_lines.push((address, decoded));
+        address += length;
+    }
+    decoded_lines
+}
+
+// renders a decoded instruction, using the label (if any) as the jump target
+pub fn format_instruction(op: isize, params: &[(isize, isize)], target: Option<&str>) -> String {
+    let info = op_info(op).unwrap();
+    let mut inputs = vec![];
+    let mut result = None;
+    for (arg_index, (mode, param)) in params.iter().enumerate() {
+        let operand = match target {
+            Some(target) if arg_index == 1 => format!("#{}", target),
+            _ => format_param(*mode, *param),
+        };
+        if info.result_arg == Some(arg_index) {
+            result = Some(operand);
+        } else {
+            inputs.push(operand);
+        }
+    }
+    let mut text = info.mnemonic.to_string();
+    if !inputs.is_empty() {
+        text += &format!(" {}", inputs.join(", "));
+    }
+    if let Some(result) = result {
+        text += &format!(" -> {}", result);
+    }
+    text
+}
+
+pub fn disassemble(intcode: &[isize]) -> Vec<Line> {
+    disassemble_with(intcode, &HashSet::new(), &HashSet::new())
+}
+
+// Like disassemble(), with hints about what the words are, e.g. from a
+// Coverage: instructions are known to start at the code addresses, and the
+// data addresses hold data unless they are also code.
+pub fn disassemble_with(
+    intcode: &[isize],
+    code: &HashSet<usize>,
+    data: &HashSet<usize>,
+) -> Vec<Line> {
+    let data: HashSet<usize> = data.difference(code).copied().collect();
+    let labels: HashSet<usize> = sweep(intcode, code, &data)
+        .iter()
+        .filter_map(|(_, decoded)| jump_target(intcode, decoded))
+        .collect();
+    let boundaries: HashSet<usize> = labels.union(code).copied().collect();
+    sweep(intcode, &boundaries, &data)
+        .into_iter()
+        .map(|(address, decoded)| {
+            let text = match &decoded {
+                Some((op, params)) => {
+                    let target = jump_target(intcode, &decoded).map(label_name);
+                    format_instruction(*op, params, target.as_deref())
+                }
+                None => format!("DATA {}", intcode[address]),
+            };
+            let length = decoded.map_or(1, |(_, params)| 1 + params.len());
+            Line {
+                address,
+                words: intcode[address..address + length].to_vec(),
+                label: labels.contains(&address).then(|| label_name(address)),
+                text,
+            }
+        })
+        .collect()
+}
+
+pub fn listing(intcode: &[isize]) -> String {
+    disassemble(intcode)
+        .iter()
+        .map(|line| {
+            let code = format!(
+                "{:>6}: {:<24} {}",
+                line.address,
+                line.words.iter().join(","),
+                line.text
+            );
+            match &line.label {
+                Some(label) => format!("{}:\n{}", label, code),
+                None => code,
+            }
+        })
+        .join("\n")
+}
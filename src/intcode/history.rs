@@ -0,0 +1,75 @@
+use super::ProgramState;
+use std::collections::VecDeque;
+
+// Undo log kept by a Program while recording, see Program::start_recording().
+// Each executed instruction gets a Step holding what it changed, which is
+// enough for Program::step_back() to undo it. Changes made by the host, with
+// write() or send() for example, are not recorded.
+#[derive(Clone, Debug)]
+pub struct Step<W = isize> {
+    pub number: usize, // instructions executed before this one
+    pub index: usize,
+    pub relative_base: isize,
+    pub writes: Vec<(usize, W)>, // (address, value before the write)
+    pub input: Option<W>,
+    pub output: Option<W>,
+    pub(super) state: ProgramState,
+    pub(super) budget: Option<usize>,
+    pub(super) queued_output: bool, // or sent to the output sink
+}
+
+#[derive(Clone, Debug)]
+pub struct History<W = isize> {
+    steps: VecDeque<Step<W>>,
+    limit: usize,
+}
+
+impl<W> History<W> {
+    pub(super) fn new(limit: usize) -> Self {
+        Self {
+            steps: VecDeque::new(),
+            limit: limit.max(1),
+        }
+    }
+
+    pub(super) fn push(&mut self, step: Step<W>) {
+        if self.steps.len() == self.limit {
+            self.steps.pop_front();
+        }
+        self.steps.push_back(step);
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.steps.clear();
+    }
+
+    pub(super) fn pop(&mut self) -> Option<Step<W>> {
+        self.steps.pop_back()
+    }
+
+    // the step being executed
+    pub(super) fn current(&mut self) -> Option<&mut Step<W>> {
+        self.steps.back_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    // oldest first
+    pub fn steps(&self) -> impl DoubleEndedIterator<Item = &Step<W>> {
+        self.steps.iter()
+    }
+
+    // the latest recorded instruction that wrote to address, if any
+    pub fn last_write(&self, address: usize) -> Option<&Step<W>> {
+        self.steps
+            .iter()
+            .rev()
+            .find(|step| step.writes.iter().any(|(written, _)| *written == address))
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{Receiver, Sender};
+
+// Where a Program reads input from once its own queue of sent values is
+// empty. Returning None makes the program wait for input; it will ask again
+// on the next step().
+pub trait IntcodeInput<W = isize> {
+    fn next_input(&mut self) -> Option<W>;
+}
+
+// Where a Program writes output instead of its own queue.
+pub trait IntcodeOutput<W = isize> {
+    fn output(&mut self, value: W);
+    // flushes what the output writes to, returning the first error it ran into
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W, F: FnMut() -> Option<W>> IntcodeInput<W> for F {
+    fn next_input(&mut self) -> Option<W> {
+        self()
+    }
+}
+
+impl<W, F: FnMut(W)> IntcodeOutput<W> for F {
+    fn output(&mut self, value: W) {
+        self(value)
+    }
+}
+
+impl<W> IntcodeInput<W> for VecDeque<W> {
+    fn next_input(&mut self) -> Option<W> {
+        self.pop_front()
+    }
+}
+
+impl<W> IntcodeOutput<W> for VecDeque<W> {
+    fn output(&mut self, value: W) {
+        self.push_back(value);
+    }
+}
+
+impl<W> IntcodeOutput<W> for Vec<W> {
+    fn output(&mut self, value: W) {
+        self.push(value);
+    }
+}
+
+// never blocks, an empty channel just makes the program wait
+impl<W> IntcodeInput<W> for Receiver<W> {
+    fn next_input(&mut self) -> Option<W> {
+        self.try_recv().ok()
+    }
+}
+
+// values sent after the receiver has gone away are dropped
+impl<W> IntcodeOutput<W> for Sender<W> {
+    fn output(&mut self, value: W) {
+        let _ = self.send(value);
+    }
+}
+
+// feeds the bytes of a text stream, e.g. a file or stdin, one at a time
+pub struct AsciiInput<R: BufRead> {
+    reader: R,
+}
+
+impl<R: BufRead> AsciiInput<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+}
+
+impl<R: BufRead> IntcodeInput for AsciiInput<R> {
+    fn next_input(&mut self) -> Option<isize> {
+        let byte = *self.reader.fill_buf().ok()?.first()?;
+        self.reader.consume(1);
+        Some(byte as isize)
+    }
+}
+
+// Prints ASCII outputs as text and anything else as a number on its own
+// line. Once writing fails, it stops writing and keeps the error for finish().
+pub struct AsciiOutput<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> AsciiOutput<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> IntcodeOutput for AsciiOutput<W> {
+    fn output(&mut self, value: isize) {
+        if self.error.is_some() {
+            return;
+        }
+        let written = if (0..128).contains(&value) {
+            write!(self.writer, "{}", value as u8 as char).and_then(|_| {
+                if value == b'\n' as isize {
+                    self.writer.flush()
+                } else {
+                    Ok(())
+                }
+            })
+        } else {
+            writeln!(self.writer, "{}", value)
+        };
+        self.error = written.err();
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.error.is_none() {
+            self.error = self.writer.flush().err();
+        }
+        self.error.take().map_or(Ok(()), Err)
+    }
+}
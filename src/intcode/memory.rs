@@ -0,0 +1,103 @@
+use super::word::Word;
+use hashbrown::HashMap;
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum MemoryKind {
+    Sparse, // every word in a hash map
+    Dense,  // a growable Vec, plus a hash map for far away addresses
+}
+
+// a write at most this many words past the end of a dense memory grows it,
+// anything further goes to the overflow map
+const MAX_GROWTH: usize = 1 << 16;
+
+#[derive(Clone, Debug)]
+pub enum Memory<W = isize> {
+    Sparse(HashMap<usize, W>),
+    Dense {
+        words: Vec<W>,
+        overflow: HashMap<usize, W>,
+    },
+}
+
+impl<W: Word> Memory<W> {
+    pub fn new(kind: MemoryKind, intcode: &[W]) -> Self {
+        match kind {
+            MemoryKind::Sparse => Memory::Sparse(
+                intcode
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (i, v.clone()))
+                    .collect(),
+            ),
+            MemoryKind::Dense => Memory::Dense {
+                words: intcode.to_vec(),
+                overflow: HashMap::new(),
+            },
+        }
+    }
+
+    pub fn kind(&self) -> MemoryKind {
+        match self {
+            Memory::Sparse(_) => MemoryKind::Sparse,
+            Memory::Dense { .. } => MemoryKind::Dense,
+        }
+    }
+
+    pub fn read(&self, address: usize) -> W {
+        let value = match self {
+            Memory::Sparse(map) => map.get(&address),
+            Memory::Dense { words, overflow } => {
+                words.get(address).or_else(|| overflow.get(&address))
+            }
+        };
+        value.cloned().unwrap_or_else(W::zero)
+    }
+
+    pub fn write(&mut self, address: usize, value: W) {
+        match self {
+            Memory::Sparse(map) => {
+                map.insert(address, value);
+            }
+            Memory::Dense { words, overflow } => {
+                if address < words.len() {
+                    words[address] = value;
+                } else if address - words.len() < MAX_GROWTH {
+                    let old_length = words.len();
+                    words.resize((address + 1).max(old_length * 2), W::zero());
+                    // move overflow words that now fall inside the Vec
+                    overflow.retain(|overflow_address, overflow_value| {
+                        if *overflow_address < words.len() {
+                            words[*overflow_address] = overflow_value.clone();
+                            false
+                        } else {
+                            true
+                        }
+                    });
+                    words[address] = value;
+                } else {
+                    overflow.insert(address, value);
+                }
+            }
+        }
+    }
+
+    // all non-zero words, in no particular order
+    pub fn non_zero(&self) -> Box<dyn Iterator<Item = (usize, W)> + '_> {
+        match self {
+            Memory::Sparse(map) => Box::new(
+                map.iter()
+                    .filter(|(_, value)| !value.is_zero())
+                    .map(|(address, value)| (*address, value.clone())),
+            ),
+            Memory::Dense { words, overflow } => Box::new(
+                words
+                    .iter()
+                    .enumerate()
+                    .chain(overflow.iter().map(|(address, value)| (*address, value)))
+                    .filter(|(_, value)| !value.is_zero())
+                    .map(|(address, value)| (address, value.clone())),
+            ),
+        }
+    }
+}
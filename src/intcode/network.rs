@@ -0,0 +1,396 @@
+use super::runtime::RuntimeError;
+use super::{asm, Program, ProgramState::*};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+// Named programs whose outputs are piped into the inputs of others, run in
+// turn on the current thread until a termination predicate holds. A node
+// connected to several others sends each output to all of them (fan-out),
+// several nodes connected to the same one interleave their outputs in the
+// order they happen (fan-in), and a node that is not connected at all keeps
+// its outputs in its own queue.
+//
+// Topology files hold one statement per line, with # starting a comment:
+//
+//     node A = amplifier.ic    # a file relative to the topology file,
+//     node B = 3,0,4,0,99      # inline Intcode, or a .asm file to assemble
+//     input A 9 0              # initial inputs
+//     A -> B, C                # an edge to each node
+//     B -> A : * 2             # the values passing through are doubled
+//     until B exited           # or: all exited, B outputs 3
+//
+// Transforms are a single operation: + - * / or % followed by a number. A
+// value the operation overflows on stops the network with an error.
+
+pub type Transform = Box<dyn Fn(isize) -> Option<isize>>; // None on overflow
+pub type Predicate = Box<dyn Fn(&Network) -> bool>;
+
+struct Edge {
+    from: usize,
+    to: usize,
+    transform: Option<Transform>,
+}
+
+// how many instructions a node runs before the next one gets its turn
+const STEPS_PER_TURN: usize = 1000;
+
+pub struct Network {
+    names: Vec<String>,
+    programs: Vec<Program>,
+    edges: Vec<Edge>,
+    until: Option<Predicate>,
+}
+
+#[derive(Debug)]
+pub struct TopologyError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TopologyError {}
+
+fn error<T>(line: usize, message: impl Into<String>) -> Result<T, TopologyError> {
+    Err(TopologyError {
+        line,
+        message: message.into(),
+    })
+}
+
+fn parse_number(line: usize, word: &str) -> Result<isize, TopologyError> {
+    word.parse()
+        .or_else(|_| error(line, format!("invalid number {:?}", word)))
+}
+
+fn parse_intcode(line: usize, words: &str) -> Result<Vec<isize>, TopologyError> {
+    words
+        .split(',')
+        .map(|word| parse_number(line, word.trim()))
+        .collect()
+}
+
+fn parse_transform(line: usize, source: &str) -> Result<Transform, TopologyError> {
+    let source = source.trim();
+    let Some(op) = source.chars().next() else {
+        return error(line, "missing transform");
+    };
+    let operand = parse_number(line, source[op.len_utf8()..].trim())?;
+    if matches!(op, '/' | '%') && operand == 0 {
+        return error(line, "division by zero");
+    }
+    Ok(match op {
+        '+' => Box::new(move |value: isize| value.checked_add(operand)),
+        '-' => Box::new(move |value: isize| value.checked_sub(operand)),
+        '*' => Box::new(move |value: isize| value.checked_mul(operand)),
+        '/' => Box::new(move |value: isize| value.checked_div(operand)),
+        '%' => Box::new(move |value: isize| value.checked_rem(operand)),
+        _ => return error(line, format!("invalid transform {:?}", source)),
+    })
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Network {
+    pub fn new() -> Self {
+        Self {
+            names: vec![],
+            programs: vec![],
+            edges: vec![],
+            until: None,
+        }
+    }
+
+    pub fn add_node(&mut self, name: &str, program: Program) {
+        assert!(self.index(name).is_none(), "duplicate node {}", name);
+        self.names.push(name.to_string());
+        self.programs.push(program);
+    }
+
+    pub fn connect(&mut self, from: &str, to: &str) {
+        self._connect(from, to, None);
+    }
+
+    // like connect(), but values are transformed on their way
+    pub fn connect_with(&mut self, from: &str, to: &str, transform: Transform) {
+        self._connect(from, to, Some(transform));
+    }
+
+    fn _connect(&mut self, from: &str, to: &str, transform: Option<Transform>) {
+        let from = self._node(from);
+        let to = self._node(to);
+        self.edges.push(Edge {
+            from,
+            to,
+            transform,
+        });
+    }
+
+    // initial input, or more input between runs
+    pub fn send(&mut self, name: &str, value: isize) {
+        self.program_mut(name).send(value);
+    }
+
+    // run() stops as soon as this holds, by default once no program can go on
+    pub fn until(&mut self, predicate: Predicate) {
+        self.until = Some(predicate);
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(|name| name.as_str())
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|node| node == name)
+    }
+
+    fn _node(&self, name: &str) -> usize {
+        self.index(name)
+            .unwrap_or_else(|| panic!("no node {}", name))
+    }
+
+    pub fn program(&self, name: &str) -> &Program {
+        &self.programs[self._node(name)]
+    }
+
+    pub fn program_mut(&mut self, name: &str) -> &mut Program {
+        let index = self._node(name);
+        &mut self.programs[index]
+    }
+
+    // Runs the nodes in turn until the termination predicate holds or all
+    // programs have exited. Errors out when a program faults or runs out of
+    // budget, or when the ones left are all waiting for input that is never
+    // going to come.
+    pub fn run(&mut self) -> Result<(), RuntimeError> {
+        loop {
+            if self.until.as_ref().is_some_and(|until| until(self)) {
+                return Ok(());
+            }
+            let mut progressed = false;
+            for index in 0..self.programs.len() {
+                progressed |= self._turn(index)?;
+            }
+            if !progressed {
+                if self
+                    .programs
+                    .iter()
+                    .all(|program| program.state() == Exited)
+                {
+                    return Ok(());
+                }
+                return Err(RuntimeError::Deadlock);
+            }
+        }
+    }
+
+    // runs one node until it outputs, blocks or its turn is over
+    fn _turn(&mut self, index: usize) -> Result<bool, RuntimeError> {
+        let connected = self.edges.iter().any(|edge| edge.from == index);
+        let program = &mut self.programs[index];
+        let mut progressed = false;
+        for _ in 0..STEPS_PER_TURN {
+            let can_run = match program.state() {
+                Running => true,
+                WaitingForInput => program.inputs().next().is_some() || program.input.is_some(),
+                Exited | Faulted => false,
+                BudgetExhausted => {
+                    return Err(RuntimeError::BudgetExhausted { program: index });
+                }
+            };
+            if !can_run {
+                break;
+            }
+            let num_steps = program.num_steps();
+            program.step().map_err(|error| RuntimeError::Fault {
+                program: index,
+                error,
+            })?;
+            if program.state() == WaitingForInput && program.num_steps() == num_steps {
+                break; // its input source had nothing either
+            }
+            progressed = true;
+            if connected && program.num_outputs() > 0 {
+                break;
+            }
+        }
+        if connected {
+            // transforms every value before taking it, so one that overflows
+            // is left with its sender
+            while let Some(value) = self.programs[index].outputs.back().copied() {
+                let mut sends = vec![];
+                for edge in self.edges.iter().filter(|edge| edge.from == index) {
+                    let value = match &edge.transform {
+                        Some(transform) => transform(value).ok_or(RuntimeError::Overflow {
+                            from: index,
+                            to: edge.to,
+                        })?,
+                        None => value,
+                    };
+                    sends.push((edge.to, value));
+                }
+                self.programs[index].receive();
+                for (to, value) in sends {
+                    self.programs[to].send(value);
+                }
+            }
+        }
+        Ok(progressed)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TopologyError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .or_else(|err| error(0, format!("{}: {}", path.display(), err)))?;
+        Network::parse(&source, path.parent().unwrap_or(Path::new(".")))
+    }
+
+    // program files are looked up relative to base_dir
+    pub fn parse(source: &str, base_dir: &Path) -> Result<Self, TopologyError> {
+        let mut network = Network::new();
+        for (line_index, text) in source.lines().enumerate() {
+            let line = line_index + 1;
+            let text = text.split('#').next().unwrap().trim();
+            if text.is_empty() {
+                continue;
+            }
+            let (keyword, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+            let rest = rest.trim();
+            let node = |network: &Network, name: &str| match network.index(name) {
+                Some(_) => Ok(()),
+                None => error(line, format!("no node {}", name)),
+            };
+            match keyword {
+                "node" => {
+                    let Some((name, program)) = rest.split_once('=') else {
+                        return error(line, "expected node NAME = PROGRAM");
+                    };
+                    let (name, program) = (name.trim(), program.trim());
+                    if name.is_empty() || name.contains(char::is_whitespace) {
+                        return error(line, format!("invalid node name {:?}", name));
+                    }
+                    if network.index(name).is_some() {
+                        return error(line, format!("duplicate node {}", name));
+                    }
+                    let intcode = if program.starts_with(|c: char| c == '-' || c.is_ascii_digit()) {
+                        parse_intcode(line, program)?
+                    } else {
+                        let path = base_dir.join(program);
+                        let source = fs::read_to_string(&path)
+                            .or_else(|err| error(line, format!("{}: {}", path.display(), err)))?;
+                        if program.ends_with(".asm") {
+                            asm::assemble(&source)
+                                .or_else(|err| error(line, format!("{}: {}", program, err)))?
+                        } else {
+                            parse_intcode(line, source.trim())?
+                        }
+                    };
+                    network.add_node(name, Program::new(&intcode));
+                }
+                "input" => {
+                    let (name, values) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                    node(&network, name)?;
+                    for value in values.split([',', ' ']).filter(|value| !value.is_empty()) {
+                        let value = parse_number(line, value)?;
+                        network.send(name, value);
+                    }
+                }
+                "until" => {
+                    let words: Vec<&str> = rest.split_whitespace().collect();
+                    let until: Predicate = match words[..] {
+                        ["all", "exited"] => {
+                            Box::new(|network| network.programs.iter().all(|p| p.state() == Exited))
+                        }
+                        [name, "exited"] => {
+                            node(&network, name)?;
+                            let name = name.to_string();
+                            Box::new(move |network| network.program(&name).state() == Exited)
+                        }
+                        [name, "outputs", count] => {
+                            node(&network, name)?;
+                            let (name, count) = (name.to_string(), parse_number(line, count)?);
+                            Box::new(move |network| {
+                                network.program(&name).num_outputs() as isize >= count
+                            })
+                        }
+                        _ => {
+                            return error(
+                                line,
+                                "expected until all exited, NODE exited or NODE outputs N",
+                            )
+                        }
+                    };
+                    network.until(until);
+                }
+                _ => {
+                    let Some((from, to)) = text.split_once("->") else {
+                        return error(line, format!("unknown statement {:?}", keyword));
+                    };
+                    let (to, transform) = match to.split_once(':') {
+                        Some((to, transform)) => (to, Some(transform)),
+                        None => (to, None),
+                    };
+                    let from = from.trim();
+                    node(&network, from)?;
+                    for to in to.split(',').map(str::trim) {
+                        node(&network, to)?;
+                        match transform {
+                            Some(transform) => {
+                                let transform = parse_transform(line, transform)?;
+                                network.connect_with(from, to, transform);
+                            }
+                            None => network.connect(from, to),
+                        }
+                    }
+                }
+            }
+        }
+        Ok(network)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflowing_values_stay_with_their_sender() {
+        let mut network = Network::new();
+        network.add_node("A", Program::new(&[104, isize::MAX, 99]));
+        network.add_node("B", Program::new(&[3, 0, 99]));
+        network.connect_with("A", "B", Box::new(|value| value.checked_mul(2)));
+        assert_eq!(
+            network.run(),
+            Err(RuntimeError::Overflow { from: 0, to: 1 })
+        );
+        let outputs: Vec<isize> = network.program("A").outputs().copied().collect();
+        assert_eq!(outputs, [isize::MAX]);
+    }
+
+    #[test]
+    fn waiting_nodes_read_their_input_source() {
+        // A's input source only has a value from its second read on, by which
+        // time A is waiting for input
+        let mut reads = 0;
+        let mut program = Program::new(&[3, 0, 4, 0, 99]);
+        program.set_input(Box::new(move || {
+            reads += 1;
+            (reads > 1).then_some(7)
+        }));
+        let mut network = Network::new();
+        network.add_node("A", program);
+        network.add_node("B", Program::new(&[1101, 0, 0, 0, 99]));
+        assert_eq!(network.run(), Ok(()));
+        let outputs: Vec<isize> = network.program("A").outputs().copied().collect();
+        assert_eq!(outputs, [7]);
+    }
+}
@@ -0,0 +1,160 @@
+use super::word::Word;
+use super::{op_info, IntcodeError, IntcodeError::*, OpInfo, Program};
+use hashbrown::HashMap;
+use std::sync::Arc;
+
+// Instructions added to the instruction set of a Program, to prototype
+// extended Intcode dialects, see ProgramBuilder::opcode(). Each one has an op
+// from 10 to 98, parameters like the built-in instructions (its OpInfo tells
+// how many and which one is written to) and a handler that executes it
+// through Operands. Programs with registered instructions always run in the
+// interpreter, even in a CompiledProgram.
+
+pub type Handler<W = isize> =
+    Arc<dyn Fn(&mut Operands<W>) -> Result<(), IntcodeError> + Send + Sync>;
+
+#[derive(Clone)]
+pub struct OpcodeRegistry<W: Word = isize> {
+    ops: HashMap<isize, (OpInfo, Handler<W>)>,
+}
+
+impl<W: Word> Default for OpcodeRegistry<W> {
+    fn default() -> Self {
+        Self {
+            ops: HashMap::new(),
+        }
+    }
+}
+
+impl<W: Word> OpcodeRegistry<W> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // panics if the op is taken or out of range, or info is not one the
+    // decoder can handle
+    pub fn register<F>(&mut self, op: isize, info: OpInfo, handler: F)
+    where
+        F: Fn(&mut Operands<W>) -> Result<(), IntcodeError> + Send + Sync + 'static,
+    {
+        assert!((10..=98).contains(&op), "op {} out of range", op);
+        assert!(self.info(op).is_none(), "op {} already registered", op);
+        assert!(
+            info.num_args <= 3,
+            "{} has too many parameters",
+            info.mnemonic
+        );
+        assert!(
+            info.result_arg
+                .is_none_or(|result_arg| result_arg < info.num_args),
+            "{} writes to a parameter it does not have",
+            info.mnemonic
+        );
+        self.ops.insert(op, (info, Arc::new(handler)));
+    }
+
+    // panics if both registries have the same op
+    pub fn extend(&mut self, other: &OpcodeRegistry<W>) {
+        for (op, (info, handler)) in &other.ops {
+            assert!(!self.ops.contains_key(op), "op {} already registered", op);
+            self.ops.insert(*op, (*info, handler.clone()));
+        }
+    }
+
+    // registered and built-in instructions alike
+    pub fn info(&self, op: isize) -> Option<OpInfo> {
+        op_info(op).or_else(|| self.ops.get(&op).map(|(info, _)| *info))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    // registered ops, in no particular order
+    pub fn ops(&self) -> impl Iterator<Item = (isize, OpInfo)> + '_ {
+        self.ops.iter().map(|(op, (info, _))| (*op, *info))
+    }
+
+    pub(super) fn handler(&self, op: isize) -> Option<Handler<W>> {
+        self.ops.get(&op).map(|(_, handler)| handler.clone())
+    }
+}
+
+// What a handler gets to execute its instruction with. Memory accesses and
+// outputs go through the program like those of built-in instructions, so they
+// are traced, profiled and can be stepped back.
+pub struct Operands<'a, W: Word = isize> {
+    pub(super) program: &'a mut Program<W>,
+    pub(super) index: usize,
+    pub(super) op_code: isize,
+    pub(super) args: [usize; 3], // resolved addresses
+    pub(super) result_arg: Option<usize>,
+    pub(super) jump: Option<usize>,
+}
+
+impl<W: Word> Operands<'_, W> {
+    // address of the instruction
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn op_code(&self) -> isize {
+        self.op_code
+    }
+
+    pub fn relative_base(&self) -> isize {
+        self.program.relative_base
+    }
+
+    // address an argument refers to
+    pub fn arg(&self, arg_index: usize) -> usize {
+        self.args[arg_index]
+    }
+
+    pub fn load(&mut self, arg_index: usize) -> W {
+        self.program._load(self.args[arg_index])
+    }
+
+    // writes to the result argument, panics if the instruction has none
+    pub fn store(&mut self, value: W) {
+        let result_arg = self.result_arg.expect("instruction without a result");
+        self.program._store(self.args[result_arg], value);
+    }
+
+    // any memory, not just the arguments
+    pub fn read(&mut self, address: usize) -> W {
+        self.program._load(address)
+    }
+
+    pub fn write(&mut self, address: usize, value: W) {
+        self.program._store(address, value);
+    }
+
+    pub fn output(&mut self, value: W) {
+        if let Some(tracer) = &mut self.program.tracer {
+            tracer.output(value.clone());
+        }
+        self.program._emit(value);
+    }
+
+    // continues at target instead of the next instruction
+    pub fn jump(&mut self, target: W) -> Result<(), IntcodeError> {
+        self.jump = Some(self.program._to_address(target.to_isize(), self.op_code)?);
+        Ok(())
+    }
+
+    pub fn overflow(&self) -> IntcodeError {
+        Overflow {
+            index: self.index,
+            op_code: self.op_code,
+        }
+    }
+
+    pub fn trap(&self, code: isize) -> IntcodeError {
+        Trap {
+            index: self.index,
+            op_code: self.op_code,
+            code,
+        }
+    }
+}
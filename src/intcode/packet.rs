@@ -0,0 +1,374 @@
+use super::runtime::RuntimeError;
+use super::{Program, ProgramState::*};
+use std::collections::VecDeque;
+
+// Simulates programs exchanging packets. A node sends a packet by outputting
+// the destination address followed by the payload words, and receives one as
+// its payload words; when it asks for input while nothing is queued for it,
+// it gets the idle input instead. Packets for addresses outside the nodes go
+// to the Router, which is also told whenever the whole network goes idle.
+//
+// Scheduling is deterministic: nodes take turns in address order, each
+// running until it asks for input, then reading one packet (or the idle input)
+// and running one more instruction. The same programs thus always produce the
+// same traffic, which makes captures replayable.
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Packet {
+    pub source: isize,
+    pub destination: isize,
+    pub payload: Vec<isize>,
+}
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct Framing {
+    pub payload_len: usize, // words following the destination address
+    pub idle_input: isize,  // read by a node with nothing queued for it
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self {
+            payload_len: 2,
+            idle_input: -1,
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum Verdict {
+    Continue,
+    Deliver(Packet),               // queue a packet for the node it is addressed to
+    Stop(isize),                   // end the simulation with this result
+    DeliverAndStop(Packet, isize), // both, in that order
+}
+
+pub trait Router {
+    // a packet addressed outside the nodes, dropped unless handled here
+    fn route(&mut self, _packet: &Packet) -> Verdict {
+        Verdict::Continue
+    }
+
+    // The network has gone idle: no packets are queued and every node has
+    // been polling for a while. If this does not deliver a packet, the
+    // simulation ends with a deadlock.
+    fn idle(&mut self) -> Verdict {
+        Verdict::Continue
+    }
+}
+
+// drops every packet addressed outside the nodes
+pub struct DropRouter;
+
+impl Router for DropRouter {}
+
+// stops at the first packet sent to an address, with its last payload word
+pub struct FirstPacket {
+    pub address: isize,
+}
+
+impl Router for FirstPacket {
+    fn route(&mut self, packet: &Packet) -> Verdict {
+        match packet.payload.last() {
+            Some(value) if packet.destination == self.address => Verdict::Stop(*value),
+            _ => Verdict::Continue,
+        }
+    }
+}
+
+// Keeps the last packet sent to its address and resends it to the target
+// whenever the network is idle. Stops once it resends a packet whose last
+// word is the same as the previous one it resent, after delivering it.
+pub struct Nat {
+    pub address: isize,
+    pub target: isize,
+    last_packet: Option<Packet>,
+    last_resent: Option<isize>,
+}
+
+impl Nat {
+    pub fn new(address: isize, target: isize) -> Self {
+        Self {
+            address,
+            target,
+            last_packet: None,
+            last_resent: None,
+        }
+    }
+}
+
+impl Router for Nat {
+    fn route(&mut self, packet: &Packet) -> Verdict {
+        if packet.destination == self.address {
+            self.last_packet = Some(packet.clone());
+        }
+        Verdict::Continue
+    }
+
+    fn idle(&mut self) -> Verdict {
+        let Some(packet) = &self.last_packet else {
+            return Verdict::Continue;
+        };
+        let value = packet.payload.last().copied();
+        let packet = Packet {
+            source: self.address,
+            destination: self.target,
+            payload: packet.payload.clone(),
+        };
+        if let Some(value) = value.filter(|value| Some(*value) == self.last_resent) {
+            return Verdict::DeliverAndStop(packet, value);
+        }
+        self.last_resent = value;
+        Verdict::Deliver(packet)
+    }
+}
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub struct NodeStats {
+    pub packets_sent: usize,
+    pub packets_received: usize,
+    pub idle_polls: usize,
+    pub instructions: usize,
+}
+
+// a packet as it was sent, with the round it was sent in
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Capture {
+    pub round: usize,
+    pub packet: Packet,
+}
+
+struct Node {
+    program: Program,
+    queue: VecDeque<Packet>,
+    idle_count: usize, // idle polls since the node last got a packet
+    stats: NodeStats,
+}
+
+pub struct PacketNetwork {
+    nodes: Vec<Node>,
+    framing: Framing,
+    idle_threshold: usize,
+    round: usize,
+    capture: Option<Vec<Capture>>,
+}
+
+impl PacketNetwork {
+    // one node per address, each booted with its address as first input
+    pub fn new(intcode: &[isize], num_nodes: usize) -> Self {
+        PacketNetwork::from_programs((0..num_nodes).map(|address| {
+            let mut program = Program::new(intcode);
+            program.send(address as isize);
+            program
+        }))
+    }
+
+    pub fn from_programs(programs: impl IntoIterator<Item = Program>) -> Self {
+        Self {
+            nodes: programs
+                .into_iter()
+                .map(|program| Node {
+                    program,
+                    queue: VecDeque::new(),
+                    idle_count: 0,
+                    stats: NodeStats::default(),
+                })
+                .collect(),
+            framing: Framing::default(),
+            idle_threshold: 1,
+            round: 0,
+            capture: None,
+        }
+    }
+
+    pub fn set_framing(&mut self, framing: Framing) {
+        self.framing = framing;
+    }
+
+    // the network counts as idle once every node has polled more than this
+    // many times in a row without getting a packet
+    pub fn set_idle_threshold(&mut self, idle_threshold: usize) {
+        self.idle_threshold = idle_threshold;
+    }
+
+    // starts recording every packet sent, by nodes and by the router
+    pub fn start_capture(&mut self) {
+        self.capture.get_or_insert_with(Vec::new);
+    }
+
+    pub fn capture(&self) -> &[Capture] {
+        self.capture.as_deref().unwrap_or(&[])
+    }
+
+    pub fn take_capture(&mut self) -> Vec<Capture> {
+        self.capture.take().unwrap_or_default()
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn program(&self, address: usize) -> &Program {
+        &self.nodes[address].program
+    }
+
+    pub fn stats(&self, address: usize) -> NodeStats {
+        self.nodes[address].stats
+    }
+
+    // number of rounds run so far
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    // queues a packet as if it had been sent, without involving the router
+    pub fn deliver(&mut self, packet: Packet) {
+        if let Some(capture) = &mut self.capture {
+            capture.push(Capture {
+                round: self.round,
+                packet: packet.clone(),
+            });
+        }
+        if let Some(node) = self._node(packet.destination) {
+            let node = &mut self.nodes[node];
+            node.idle_count = 0;
+            node.queue.push_back(packet);
+        }
+    }
+
+    fn _node(&self, address: isize) -> Option<usize> {
+        usize::try_from(address)
+            .ok()
+            .filter(|address| *address < self.nodes.len())
+    }
+
+    // Runs rounds until the router stops the simulation and returns its
+    // result. Fails if a node faults or runs out of budget, if the network
+    // stays idle without the router doing anything about it, or if every node
+    // has exited.
+    pub fn run(&mut self, router: &mut dyn Router) -> Result<isize, RuntimeError> {
+        loop {
+            for address in 0..self.nodes.len() {
+                for packet in self._turn(address)? {
+                    self.deliver(packet.clone());
+                    if self._node(packet.destination).is_none() {
+                        if let Some(result) = self._apply(router.route(&packet)) {
+                            return Ok(result);
+                        }
+                    }
+                }
+            }
+            self.round += 1;
+            if self.nodes.iter().all(|node| node.program.state() == Exited) {
+                return Err(RuntimeError::Deadlock);
+            }
+            let idle = self.nodes.iter().all(|node| {
+                node.queue.is_empty()
+                    && (node.idle_count > self.idle_threshold || node.program.state() == Exited)
+            });
+            if idle {
+                let verdict = router.idle();
+                if verdict == Verdict::Continue {
+                    return Err(RuntimeError::Deadlock);
+                }
+                if let Some(result) = self._apply(verdict) {
+                    return Ok(result);
+                }
+            }
+        }
+    }
+
+    fn _apply(&mut self, verdict: Verdict) -> Option<isize> {
+        match verdict {
+            Verdict::Continue => None,
+            Verdict::Deliver(packet) => {
+                self.deliver(packet);
+                None
+            }
+            Verdict::Stop(result) => Some(result),
+            Verdict::DeliverAndStop(packet, result) => {
+                self.deliver(packet);
+                Some(result)
+            }
+        }
+    }
+
+    // runs a node until it asks for input, hands it its next packet or the
+    // idle input, and returns the packets it sent meanwhile
+    fn _turn(&mut self, address: usize) -> Result<Vec<Packet>, RuntimeError> {
+        let framing = self.framing;
+        let node = &mut self.nodes[address];
+        let fault = |error| RuntimeError::Fault {
+            program: address,
+            error,
+        };
+        let num_steps = node.program.num_steps();
+        while node.program.state() == Running {
+            node.program.step().map_err(fault)?;
+        }
+        if node.program.state() == BudgetExhausted {
+            return Err(RuntimeError::BudgetExhausted { program: address });
+        }
+        if node.program.state() == WaitingForInput {
+            match node.queue.pop_front() {
+                Some(packet) => {
+                    for value in packet.payload {
+                        node.program.send(value);
+                    }
+                    node.idle_count = 0;
+                    node.stats.packets_received += 1;
+                }
+                None => {
+                    node.program.send(framing.idle_input);
+                    node.idle_count += 1;
+                    node.stats.idle_polls += 1;
+                }
+            }
+            node.program.step().map_err(fault)?;
+        }
+        node.stats.instructions += node.program.num_steps() - num_steps;
+        let mut packets = vec![];
+        while node.program.num_outputs() > framing.payload_len {
+            let destination = node.program.receive().unwrap();
+            let payload = (0..framing.payload_len)
+                .map(|_| node.program.receive().unwrap())
+                .collect();
+            packets.push(Packet {
+                source: address as isize,
+                destination,
+                payload,
+            });
+        }
+        node.stats.packets_sent += packets.len();
+        Ok(packets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nat_captures_the_repeated_packet() {
+        let input = include_str!("../../data/day23.txt");
+        let intcode: Vec<isize> = input
+            .trim()
+            .split(',')
+            .map(|n| n.parse().unwrap())
+            .collect();
+        let mut network = PacketNetwork::new(&intcode, 50);
+        network.start_capture();
+        let result = network.run(&mut Nat::new(255, 0)).unwrap();
+        let capture = network.take_capture();
+        let resent: Vec<&Packet> = capture
+            .iter()
+            .map(|capture| &capture.packet)
+            .filter(|packet| packet.source == 255)
+            .collect();
+        let (last, previous) = (resent[resent.len() - 1], resent[resent.len() - 2]);
+        assert_eq!(capture.last().unwrap().packet, *last);
+        assert_eq!(last.destination, 0);
+        assert_eq!(last.payload.last(), Some(&result));
+        assert_eq!(previous.payload.last(), Some(&result));
+    }
+}
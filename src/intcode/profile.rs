@@ -0,0 +1,152 @@
+use super::{disasm, op_info};
+use hashbrown::HashMap;
+use itertools::Itertools;
+use std::fmt::Write;
+
+// Execution counts gathered by a Program while profiling, see
+// Program::start_profiling(). Memory accesses only include those made by the
+// program itself, not the host's read() and write() calls.
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    pub executions: HashMap<usize, usize>, // by instruction address
+    pub ops: HashMap<isize, usize>,        // by op, i.e. op code % 100
+    pub waits: HashMap<usize, usize>,      // steps spent waiting, by address
+    pub jumps: HashMap<usize, usize>,      // conditional jumps taken, by address
+    pub reads: HashMap<usize, usize>,      // by memory address
+    pub writes: HashMap<usize, usize>,
+}
+
+fn mnemonic(op: isize) -> String {
+    op_info(op).map_or(format!("op {}", op), |info| info.mnemonic.to_string())
+}
+
+fn percent(count: usize, total: usize) -> f64 {
+    100.0 * count as f64 / total.max(1) as f64
+}
+
+impl Profile {
+    pub fn num_instructions(&self) -> usize {
+        self.executions.values().sum()
+    }
+
+    pub fn num_waits(&self) -> usize {
+        self.waits.values().sum()
+    }
+
+    // hottest addresses first
+    fn _top(counts: &HashMap<usize, usize>, limit: usize) -> Vec<(usize, usize)> {
+        counts
+            .iter()
+            .map(|(address, count)| (*address, *count))
+            .sorted_by_key(|(address, count)| (std::cmp::Reverse(*count), *address))
+            .take(limit)
+            .collect()
+    }
+
+    // totals per op, the most executed instructions and memory hot spots,
+    // limit lines each
+    pub fn summary(&self, limit: usize) -> String {
+        let total = self.num_instructions();
+        let mut report = String::new();
+        writeln!(
+            report,
+            "{} instructions, {} steps waiting for input",
+            total,
+            self.num_waits()
+        )
+        .unwrap();
+        writeln!(report, "\nby op:").unwrap();
+        for (op, count) in self
+            .ops
+            .iter()
+            .sorted_by_key(|(op, count)| (std::cmp::Reverse(**count), **op))
+        {
+            writeln!(
+                report,
+                "    {:<4} {:>12} {:>6.2}%",
+                mnemonic(*op),
+                count,
+                percent(*count, total)
+            )
+            .unwrap();
+        }
+        writeln!(report, "\nhottest instructions:").unwrap();
+        for (address, count) in Profile::_top(&self.executions, limit) {
+            writeln!(
+                report,
+                "{:>10} {:>6.2}% &{}",
+                count,
+                percent(count, total),
+                address
+            )
+            .unwrap();
+        }
+        if !self.waits.is_empty() {
+            writeln!(report, "\nwaiting for input:").unwrap();
+            for (address, count) in Profile::_top(&self.waits, limit) {
+                writeln!(report, "{:>10} &{}", count, address).unwrap();
+            }
+        }
+        let accesses: HashMap<usize, usize> = self
+            .reads
+            .keys()
+            .chain(self.writes.keys())
+            .map(|address| {
+                let count =
+                    self.reads.get(address).unwrap_or(&0) + self.writes.get(address).unwrap_or(&0);
+                (*address, count)
+            })
+            .collect();
+        writeln!(report, "\nmemory hot spots:      reads     writes").unwrap();
+        for (address, _) in Profile::_top(&accesses, limit) {
+            writeln!(
+                report,
+                "{:>10} {:>16} {:>10}",
+                format!("&{}", address),
+                self.reads.get(&address).unwrap_or(&0),
+                self.writes.get(&address).unwrap_or(&0)
+            )
+            .unwrap();
+        }
+        report
+    }
+
+    // the disassembly listing of intcode, with execution counts in front of
+    // each instruction and memory access counts in front of data words
+    pub fn annotated_listing(&self, intcode: &[isize]) -> String {
+        let total = self.num_instructions();
+        let mut report = String::new();
+        for line in disasm::disassemble(intcode) {
+            if let Some(label) = &line.label {
+                writeln!(report, "{:>19}{}:", "", label).unwrap();
+            }
+            let annotation = match self.executions.get(&line.address) {
+                Some(count) => format!("{:>10} {:>6.2}%", count, percent(*count, total)),
+                None => {
+                    let words = line.address..line.address + line.words.len();
+                    let count = |counts: &HashMap<usize, usize>| -> usize {
+                        words
+                            .clone()
+                            .filter_map(|address| counts.get(&address))
+                            .sum()
+                    };
+                    let (reads, writes) = (count(&self.reads), count(&self.writes));
+                    match reads + writes {
+                        0 => String::new(),
+                        _ => format!("{:>8}r {:>7}w", reads, writes),
+                    }
+                }
+            };
+            writeln!(
+                report,
+                "{:<18} {:>6}: {:<24} {}",
+                annotation,
+                line.address,
+                line.words.iter().join(","),
+                line.text
+            )
+            .unwrap();
+        }
+        report
+    }
+}
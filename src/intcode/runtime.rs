@@ -0,0 +1,328 @@
+use super::{IntcodeError, Program, ProgramState::*};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread;
+
+// Runs each program on its own thread, with the outputs of a program piped
+// into the inputs of the programs it is connected to through bounded queues.
+// A program blocks when it needs input and its queue is empty, or when it
+// outputs to a full queue. The run ends when every program has exited, when
+// one faults or runs out of budget, or when all the remaining ones are blocked
+// on each other.
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum RuntimeError {
+    Fault { program: usize, error: IntcodeError },
+    BudgetExhausted { program: usize },
+    Deadlock,
+    Overflow { from: usize, to: usize }, // transforming a value sent between them
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeError::Fault { program, error } => write!(f, "program #{}: {}", program, error),
+            RuntimeError::BudgetExhausted { program } => {
+                write!(f, "program #{}: budget exhausted", program)
+            }
+            RuntimeError::Deadlock => write!(f, "all programs are blocked"),
+            RuntimeError::Overflow { from, to } => write!(
+                f,
+                "overflow transforming a value from program #{} to #{}",
+                from, to
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+#[derive(Copy, Clone)]
+enum Wait {
+    Input,
+    Output(usize), // room in the input queue of this program
+}
+
+struct Queues {
+    queues: Vec<VecDeque<isize>>, // inputs of each program
+    waits: Vec<Option<Wait>>,
+    done: Vec<bool>,
+    error: Option<RuntimeError>,
+}
+
+struct Shared {
+    queues: Mutex<Queues>,
+    changed: Condvar,
+    stopped: AtomicBool,
+    capacity: usize,
+}
+
+impl Shared {
+    fn _is_blocked(&self, queues: &Queues, id: usize) -> bool {
+        match queues.waits[id] {
+            _ if queues.done[id] => true,
+            None => false,
+            Some(Wait::Input) => queues.queues[id].is_empty(),
+            Some(Wait::Output(to)) => !queues.done[to] && queues.queues[to].len() >= self.capacity,
+        }
+    }
+
+    // some programs are still running, but none of them can make progress
+    fn _is_deadlocked(&self, queues: &Queues) -> bool {
+        queues.done.contains(&false)
+            && (0..queues.done.len()).all(|id| self._is_blocked(queues, id))
+    }
+
+    fn _stop(&self, queues: &mut Queues, error: RuntimeError) {
+        queues.error.get_or_insert(error);
+        self.stopped.store(true, Ordering::Relaxed);
+        self.changed.notify_all();
+    }
+
+    // waits for something to change, unless that can no longer happen
+    fn _block<'a>(
+        &self,
+        mut queues: MutexGuard<'a, Queues>,
+        id: usize,
+        wait: Wait,
+    ) -> MutexGuard<'a, Queues> {
+        queues.waits[id] = Some(wait);
+        if self._is_deadlocked(&queues) {
+            self._stop(&mut queues, RuntimeError::Deadlock);
+            return queues;
+        }
+        let mut queues = self.changed.wait(queues).unwrap();
+        queues.waits[id] = None;
+        queues
+    }
+
+    fn receive(&self, id: usize) -> Option<isize> {
+        let mut queues = self.queues.lock().unwrap();
+        while queues.error.is_none() {
+            if let Some(value) = queues.queues[id].pop_front() {
+                self.changed.notify_all();
+                return Some(value);
+            }
+            queues = self._block(queues, id, Wait::Input);
+        }
+        None
+    }
+
+    fn send(&self, id: usize, to: usize, value: isize) -> bool {
+        let mut queues = self.queues.lock().unwrap();
+        while queues.error.is_none() {
+            // nobody will ever read from an exited program's queue, so
+            // rather than waiting for room, only keep its latest values
+            if queues.done[to] && queues.queues[to].len() >= self.capacity {
+                queues.queues[to].pop_front();
+            }
+            if queues.queues[to].len() < self.capacity {
+                queues.queues[to].push_back(value);
+                self.changed.notify_all();
+                return true;
+            }
+            queues = self._block(queues, id, Wait::Output(to));
+        }
+        false
+    }
+
+    fn finish(&self, id: usize, error: Option<RuntimeError>) {
+        let mut queues = self.queues.lock().unwrap();
+        queues.done[id] = true;
+        match error {
+            Some(error) => self._stop(&mut queues, error),
+            None if self._is_deadlocked(&queues) => self._stop(&mut queues, RuntimeError::Deadlock),
+            None => self.changed.notify_all(),
+        }
+    }
+
+    fn run(&self, id: usize, program: &mut Program, destinations: &[usize]) {
+        while !self.stopped.load(Ordering::Relaxed) {
+            match program.state() {
+                Exited | Faulted => break,
+                BudgetExhausted => {
+                    let error = RuntimeError::BudgetExhausted { program: id };
+                    return self.finish(id, Some(error));
+                }
+                Running | WaitingForInput => {}
+            }
+            if let Err(error) = program.step() {
+                return self.finish(id, Some(RuntimeError::Fault { program: id, error }));
+            }
+            if !destinations.is_empty() {
+                while let Some(value) = program.receive() {
+                    for to in destinations {
+                        if !self.send(id, *to, value) {
+                            return self.finish(id, None);
+                        }
+                    }
+                }
+            }
+            if program.state() == WaitingForInput {
+                match self.receive(id) {
+                    Some(value) => program.send(value),
+                    None => break,
+                }
+            }
+        }
+        self.finish(id, None);
+    }
+}
+
+pub struct Runtime {
+    programs: Vec<Program>,
+    pipes: Vec<Vec<usize>>, // where the outputs of each program go
+    capacity: usize,
+}
+
+impl Runtime {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "queues need room for at least one value");
+        Self {
+            programs: vec![],
+            pipes: vec![],
+            capacity,
+        }
+    }
+
+    // returns the id of the program, used by connect()
+    pub fn add(&mut self, program: Program) -> usize {
+        self.programs.push(program);
+        self.pipes.push(vec![]);
+        self.programs.len() - 1
+    }
+
+    // outputs of a program stay in its own queue unless it is connected,
+    // a program connected more than once sends every output to all of them
+    pub fn connect(&mut self, from: usize, to: usize) {
+        assert!(to < self.programs.len(), "no program #{}", to);
+        self.pipes[from].push(to);
+    }
+
+    pub fn programs(&self) -> &[Program] {
+        &self.programs
+    }
+
+    pub fn programs_mut(&mut self) -> &mut [Program] {
+        &mut self.programs
+    }
+
+    pub fn into_programs(self) -> Vec<Program> {
+        self.programs
+    }
+
+    // Values still in a queue when the run ends, for instance sent to a
+    // program that had already exited, are handed back to that program with
+    // send(). Can be called again after adding more input.
+    pub fn run(&mut self) -> Result<(), RuntimeError> {
+        let shared = Shared {
+            queues: Mutex::new(Queues {
+                queues: vec![VecDeque::new(); self.programs.len()],
+                waits: vec![None; self.programs.len()],
+                done: vec![false; self.programs.len()],
+                error: None,
+            }),
+            changed: Condvar::new(),
+            stopped: AtomicBool::new(false),
+            capacity: self.capacity,
+        };
+        thread::scope(|scope| {
+            for (id, program) in self.programs.iter_mut().enumerate() {
+                let (shared, destinations) = (&shared, &self.pipes[id]);
+                scope.spawn(move || shared.run(id, program, destinations));
+            }
+        });
+        let queues = shared.queues.into_inner().unwrap();
+        for (program, queue) in self.programs.iter_mut().zip(queues.queues) {
+            for value in queue {
+                program.send(value);
+            }
+        }
+        match queues.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hashbrown::HashMap;
+    use itertools::Itertools;
+
+    fn day07() -> Vec<isize> {
+        let input = include_str!("../../data/day07.txt");
+        input
+            .trim()
+            .split(',')
+            .map(|n| n.parse().unwrap())
+            .collect()
+    }
+
+    // the day 7 feedback loop, on the single-threaded scheduler
+    fn feedback_loop(intcode: &[isize], phases: &[isize]) -> isize {
+        let mut programs: Vec<Program> = phases
+            .iter()
+            .map(|phase| {
+                let mut program = Program::new(intcode);
+                program.send(*phase);
+                program
+            })
+            .collect();
+        programs[0].send(0);
+        let pipes: HashMap<usize, Vec<usize>> = (0..5).map(|i| (i, vec![(i + 1) % 5])).collect();
+        Program::run_until(&mut programs, &pipes, |programs| {
+            programs[4].state() == Exited
+        })
+        .unwrap();
+        *programs[0].inputs().last().unwrap()
+    }
+
+    #[test]
+    fn feedback_loops() {
+        let intcode = day07();
+        for phases in (5..10).permutations(5).step_by(7) {
+            let mut runtime = Runtime::new(1);
+            for phase in &phases {
+                let mut program = Program::new(&intcode);
+                program.send(*phase);
+                runtime.add(program);
+            }
+            for i in 0..5 {
+                runtime.connect(i, (i + 1) % 5);
+            }
+            runtime.programs_mut()[0].send(0);
+            runtime.run().unwrap();
+            let signal = *runtime.programs()[0].inputs().last().unwrap();
+            assert_eq!(signal, feedback_loop(&intcode, &phases));
+        }
+    }
+
+    #[test]
+    fn deadlock() {
+        // both wait for the other one's output before producing their own
+        let mut runtime = Runtime::new(1);
+        for _ in 0..2 {
+            runtime.add(Program::new(&[3, 7, 4, 7, 1105, 1, 0, 0]));
+        }
+        runtime.connect(0, 1);
+        runtime.connect(1, 0);
+        assert_eq!(runtime.run(), Err(RuntimeError::Deadlock));
+    }
+
+    #[test]
+    fn faults() {
+        let mut runtime = Runtime::new(4);
+        let sender = runtime.add(Program::new(&[104, 1, 104, 2, 99]));
+        let receiver = runtime.add(Program::new(&[3, 9, 3, 10, 1, -1, 0, 0, 99]));
+        runtime.connect(sender, receiver);
+        let Err(RuntimeError::Fault { program, error }) = runtime.run() else {
+            panic!("expected a fault");
+        };
+        assert_eq!(program, receiver);
+        assert_eq!(error.index(), 4);
+    }
+}
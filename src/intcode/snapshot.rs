@@ -0,0 +1,178 @@
+use super::{varint, IntcodeError, IntcodeError::*, ProgramState, ProgramState::*};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+// everything needed to resume a Program, except its tracer
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Snapshot {
+    pub(super) memory: Vec<(usize, isize)>, // non-zero words, sorted by address
+    pub(super) index: usize,
+    pub(super) relative_base: isize,
+    pub(super) inputs: VecDeque<isize>,
+    pub(super) outputs: VecDeque<isize>,
+    pub(super) state: ProgramState,
+    pub(super) fault: Option<IntcodeError>,
+}
+
+// Snapshot files start with MAGIC, followed by varints: index, relative base,
+// state, fault (0 for none, else its kind, index, op code and detail), the
+// input and output queues (length then values, in queue order) and finally
+// the number of non-zero memory words followed by (address gap, value) pairs.
+const MAGIC: &[u8] = b"ICSNAP\x01";
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_usize<R: Read>(reader: &mut R) -> io::Result<usize> {
+    match varint::decode(reader)? {
+        value if value < 0 => Err(invalid_data("negative address")),
+        value => Ok(value as usize),
+    }
+}
+
+fn read_queue<R: Read>(reader: &mut R) -> io::Result<VecDeque<isize>> {
+    let length = read_usize(reader)?;
+    (0..length).map(|_| varint::decode(reader)).collect()
+}
+
+impl Snapshot {
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut buffer = MAGIC.to_vec();
+        let state = match self.state {
+            Running => 0,
+            WaitingForInput => 1,
+            Exited => 2,
+            Faulted => 3,
+            BudgetExhausted => 4,
+        };
+        let fault = match self.fault {
+            None => [0, 0, 0, 0],
+            Some(InvalidOpCode { index, op_code }) => [1, index as isize, op_code, 0],
+            Some(InvalidParameterMode {
+                index,
+                op_code,
+                mode,
+            }) => [2, index as isize, op_code, mode],
+            Some(NegativeAddress {
+                index,
+                op_code,
+                address,
+            }) => [3, index as isize, op_code, address],
+            Some(WriteToImmediate { index, op_code }) => [4, index as isize, op_code, 0],
+            Some(Overflow { index, op_code }) => [5, index as isize, op_code, 0],
+            Some(Trap {
+                index,
+                op_code,
+                code,
+            }) => [6, index as isize, op_code, code],
+        };
+        let header = [self.index as isize, self.relative_base, state];
+        for value in header.into_iter().chain(fault) {
+            varint::encode(value, &mut buffer);
+        }
+        for queue in [&self.inputs, &self.outputs] {
+            varint::encode(queue.len() as isize, &mut buffer);
+            for value in queue {
+                varint::encode(*value, &mut buffer);
+            }
+        }
+        varint::encode(self.memory.len() as isize, &mut buffer);
+        let mut next_address = 0;
+        for (address, value) in &self.memory {
+            varint::encode((address - next_address) as isize, &mut buffer);
+            varint::encode(*value, &mut buffer);
+            next_address = address.saturating_add(1);
+        }
+        writer.write_all(&buffer)?;
+        writer.flush()
+    }
+
+    pub fn read_from<R: Read>(reader: R) -> io::Result<Self> {
+        let mut reader = BufReader::new(reader);
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid_data("not an Intcode snapshot"));
+        }
+        let reader = &mut reader;
+        let index = read_usize(reader)?;
+        let relative_base = varint::decode(reader)?;
+        let state = match varint::decode(reader)? {
+            0 => Running,
+            1 => WaitingForInput,
+            2 => Exited,
+            3 => Faulted,
+            4 => BudgetExhausted,
+            _ => return Err(invalid_data("invalid program state")),
+        };
+        let kind = varint::decode(reader)?;
+        let fault_index = read_usize(reader)?;
+        let op_code = varint::decode(reader)?;
+        let detail = varint::decode(reader)?;
+        let fault = match kind {
+            0 => None,
+            1 => Some(InvalidOpCode {
+                index: fault_index,
+                op_code,
+            }),
+            2 => Some(InvalidParameterMode {
+                index: fault_index,
+                op_code,
+                mode: detail,
+            }),
+            3 => Some(NegativeAddress {
+                index: fault_index,
+                op_code,
+                address: detail,
+            }),
+            4 => Some(WriteToImmediate {
+                index: fault_index,
+                op_code,
+            }),
+            5 => Some(Overflow {
+                index: fault_index,
+                op_code,
+            }),
+            6 => Some(Trap {
+                index: fault_index,
+                op_code,
+                code: detail,
+            }),
+            _ => return Err(invalid_data("invalid fault")),
+        };
+        if (state == Faulted) != fault.is_some() {
+            return Err(invalid_data("inconsistent fault"));
+        }
+        let inputs = read_queue(reader)?;
+        let outputs = read_queue(reader)?;
+        let mut memory = vec![];
+        let mut next_address: usize = 0;
+        for _ in 0..read_usize(reader)? {
+            let address = next_address
+                .checked_add(read_usize(reader)?)
+                .ok_or_else(|| invalid_data("invalid address"))?;
+            memory.push((address, varint::decode(reader)?));
+            next_address = address.saturating_add(1);
+        }
+        Ok(Self {
+            memory,
+            index,
+            relative_base,
+            inputs,
+            outputs,
+            state,
+            fault,
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_from(File::open(path)?)
+    }
+}
@@ -0,0 +1,826 @@
+use super::{op_info, OpInfo, Program};
+use hashbrown::HashMap;
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::fmt;
+
+// Symbolic execution: runs a program with its inputs as symbols in0, in1, ...
+// and computes values as polynomials of them. Whenever a comparison or a
+// conditional jump depends on the inputs, both outcomes are explored, each
+// path collecting the conditions it assumed, so that the paths reaching a
+// target describe which inputs get there.
+//
+// Before a path assumes a condition, its conditions are checked for
+// consistency as linear inequalities over the monomials. That rules out most
+// infeasible paths, but not all of them.
+//
+// Addresses, jump targets and relative base adjustments have to be concrete;
+// a path where one of them depends on the inputs is given up on, as are paths
+// running past the step limit and paths whose coefficients overflow.
+
+// a product of inputs, by index, sorted
+type Monomial = Vec<usize>;
+
+// sum of monomials with their (non-zero) coefficients, the constant term
+// being the empty monomial
+#[derive(Eq, PartialEq, Hash, Clone, Debug, Default)]
+pub struct Poly(BTreeMap<Monomial, isize>);
+
+impl Poly {
+    pub fn constant(value: isize) -> Self {
+        Poly::monomial(vec![], value)
+    }
+
+    pub fn input(index: usize) -> Self {
+        Poly::monomial(vec![index], 1)
+    }
+
+    fn monomial(monomial: Monomial, coefficient: isize) -> Self {
+        let mut terms = BTreeMap::new();
+        if coefficient != 0 {
+            terms.insert(monomial, coefficient);
+        }
+        Poly(terms)
+    }
+
+    pub fn as_constant(&self) -> Option<isize> {
+        match self.0.len() {
+            0 => Some(0),
+            1 => self.0.get(&vec![]).copied(),
+            _ => None,
+        }
+    }
+
+    fn constant_term(&self) -> isize {
+        self.0.get(&vec![]).copied().unwrap_or(0)
+    }
+
+    fn add(&self, other: &Poly) -> Option<Poly> {
+        let mut terms = self.0.clone();
+        for (monomial, coefficient) in &other.0 {
+            let sum = terms
+                .get(monomial)
+                .unwrap_or(&0)
+                .checked_add(*coefficient)?;
+            if sum == 0 {
+                terms.remove(monomial);
+            } else {
+                terms.insert(monomial.clone(), sum);
+            }
+        }
+        Some(Poly(terms))
+    }
+
+    fn scale(&self, factor: isize) -> Option<Poly> {
+        let mut terms = BTreeMap::new();
+        if factor != 0 {
+            for (monomial, coefficient) in &self.0 {
+                terms.insert(monomial.clone(), coefficient.checked_mul(factor)?);
+            }
+        }
+        Some(Poly(terms))
+    }
+
+    fn sub(&self, other: &Poly) -> Option<Poly> {
+        self.add(&other.scale(-1)?)
+    }
+
+    fn mul(&self, other: &Poly) -> Option<Poly> {
+        let mut product = Poly::default();
+        for (a, a_coefficient) in &self.0 {
+            for (b, b_coefficient) in &other.0 {
+                let monomial = a.iter().chain(b).copied().sorted().collect();
+                let coefficient = a_coefficient.checked_mul(*b_coefficient)?;
+                product = product.add(&Poly::monomial(monomial, coefficient))?;
+            }
+        }
+        Some(product)
+    }
+
+    // None if an input is missing or the arithmetic overflows
+    pub fn eval(&self, inputs: &[isize]) -> Option<isize> {
+        let mut sum: isize = 0;
+        for (monomial, coefficient) in &self.0 {
+            let mut product = *coefficient;
+            for index in monomial {
+                product = product.checked_mul(*inputs.get(*index)?)?;
+            }
+            sum = sum.checked_add(product)?;
+        }
+        Some(sum)
+    }
+
+    // the positive and the negated negative terms, to write p < 0 as a < b
+    fn split(&self) -> (Poly, Poly) {
+        let mut sides = (Poly::default(), Poly::default());
+        for (monomial, coefficient) in &self.0 {
+            match *coefficient > 0 {
+                true => sides.0 .0.insert(monomial.clone(), *coefficient),
+                false => sides.1 .0.insert(monomial.clone(), -coefficient),
+            };
+        }
+        sides
+    }
+}
+
+impl fmt::Display for Poly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "0");
+        }
+        // highest degree first, the constant term last
+        let terms = self
+            .0
+            .iter()
+            .sorted_by_key(|(monomial, _)| std::cmp::Reverse(monomial.len()));
+        for (index, (monomial, coefficient)) in terms.enumerate() {
+            let sign = match (index, *coefficient < 0) {
+                (0, true) => "-",
+                (0, false) => "",
+                (_, true) => " - ",
+                (_, false) => " + ",
+            };
+            let magnitude = coefficient.unsigned_abs();
+            let factors = monomial
+                .iter()
+                .dedup_with_count()
+                .map(|(power, index)| match power {
+                    1 => format!("in{}", index),
+                    _ => format!("in{}^{}", index, power),
+                })
+                .join("*");
+            match (magnitude, factors.is_empty()) {
+                (_, true) => write!(f, "{}{}", sign, magnitude)?,
+                (1, false) => write!(f, "{}{}", sign, factors)?,
+                (_, false) => write!(f, "{}{}*{}", sign, magnitude, factors)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub enum Relation {
+    Negative, // < 0
+    NonNegative,
+    Zero,
+    NonZero,
+}
+
+use Relation::*;
+
+// a polynomial in some relation to 0
+#[derive(Eq, PartialEq, Hash, Clone, Debug)]
+pub struct Condition {
+    pub poly: Poly,
+    pub relation: Relation,
+}
+
+impl Condition {
+    // Err if the condition does not depend on the inputs, with whether it
+    // holds, or overflows, with None
+    fn new(poly: Poly, relation: Relation) -> Result<Condition, Option<bool>> {
+        if let Some(value) = poly.as_constant() {
+            return Err(Some(match relation {
+                Negative => value < 0,
+                NonNegative => value >= 0,
+                Zero => value == 0,
+                NonZero => value != 0,
+            }));
+        }
+        // p == 0 and -p == 0 are the same condition
+        let poly = match (relation, poly.0.values().next()) {
+            (Zero | NonZero, Some(coefficient)) if *coefficient < 0 => {
+                poly.scale(-1).ok_or(None)?
+            }
+            _ => poly,
+        };
+        Ok(Condition { poly, relation })
+    }
+
+    pub fn negated(&self) -> Condition {
+        let relation = match self.relation {
+            Negative => NonNegative,
+            NonNegative => Negative,
+            Zero => NonZero,
+            NonZero => Zero,
+        };
+        Condition {
+            poly: self.poly.clone(),
+            relation,
+        }
+    }
+
+    pub fn eval(&self, inputs: &[isize]) -> Option<bool> {
+        let value = self.poly.eval(inputs)?;
+        Some(match self.relation {
+            Negative => value < 0,
+            NonNegative => value >= 0,
+            Zero => value == 0,
+            NonZero => value != 0,
+        })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operator = match self.relation {
+            Negative => "<",
+            NonNegative => ">=",
+            Zero => "==",
+            NonZero => "!=",
+        };
+        let (a, b) = self.poly.split();
+        write!(f, "{} {} {}", a, operator, b)
+    }
+}
+
+// Whether the conditions can all hold, as far as Fourier-Motzkin elimination
+// can tell with each monomial taken as an integer variable of its own. Gives
+// up, and assumes they can, when the inequalities get too many or too large.
+fn feasible(conditions: &[Condition]) -> bool {
+    const MAX_INEQUALITIES: usize = 256;
+    // sum of coefficient * monomial <= bound
+    type Inequality = (BTreeMap<Monomial, i128>, i128);
+    fn inequality(poly: &Poly, sign: i128, bound: i128) -> Inequality {
+        let terms = poly
+            .0
+            .iter()
+            .filter(|(monomial, _)| !monomial.is_empty())
+            .map(|(monomial, coefficient)| (monomial.clone(), sign * *coefficient as i128))
+            .collect();
+        (terms, bound - sign * poly.constant_term() as i128)
+    }
+    // divides by the coefficients' gcd, rounding the bound down since the
+    // monomials are integers
+    fn normalize((terms, bound): Inequality) -> Inequality {
+        let gcd = terms.values().fold(0, |gcd, coefficient| {
+            let (mut a, mut b) = (gcd, coefficient.abs());
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            a
+        });
+        if gcd <= 1 {
+            return (terms, bound);
+        }
+        let terms = terms
+            .into_iter()
+            .map(|(monomial, coefficient)| (monomial, coefficient / gcd))
+            .collect();
+        (terms, bound.div_euclid(gcd))
+    }
+    let mut inequalities: Vec<Inequality> = vec![];
+    for Condition { poly, relation } in conditions {
+        match relation {
+            Negative => inequalities.push(inequality(poly, 1, -1)),
+            NonNegative => inequalities.push(inequality(poly, -1, 0)),
+            Zero => {
+                inequalities.push(inequality(poly, 1, 0));
+                inequalities.push(inequality(poly, -1, 0));
+            }
+            NonZero => {}
+        }
+    }
+    loop {
+        let mut remaining = vec![];
+        for (terms, bound) in inequalities.into_iter().map(normalize) {
+            if terms.is_empty() {
+                if bound < 0 {
+                    return false;
+                }
+            } else if !remaining.contains(&(terms.clone(), bound)) {
+                remaining.push((terms, bound));
+            }
+        }
+        let Some(variable) = remaining
+            .first()
+            .map(|(terms, _)| terms.keys().next().unwrap().clone())
+        else {
+            return true;
+        };
+        if remaining.len() > MAX_INEQUALITIES {
+            return true;
+        }
+        let coefficient = |inequality: &Inequality| *inequality.0.get(&variable).unwrap_or(&0);
+        let (with, without): (Vec<_>, Vec<_>) = remaining
+            .into_iter()
+            .partition(|inequality| coefficient(inequality) != 0);
+        let (upper, lower): (Vec<_>, Vec<_>) = with
+            .into_iter()
+            .partition(|inequality| coefficient(inequality) > 0);
+        inequalities = without;
+        for a in &upper {
+            for b in &lower {
+                // a * cb + b * ca, where the variable cancels out
+                let (ca, cb) = (coefficient(a), -coefficient(b));
+                let mut terms: BTreeMap<Monomial, i128> = BTreeMap::new();
+                for ((inequality_terms, _), factor) in [(a, cb), (b, ca)] {
+                    for (monomial, value) in inequality_terms {
+                        let entry = terms.entry(monomial.clone()).or_default();
+                        let sum = value
+                            .checked_mul(factor)
+                            .and_then(|value| entry.checked_add(value));
+                        let Some(sum) = sum else {
+                            return true;
+                        };
+                        *entry = sum;
+                    }
+                }
+                terms.retain(|_, value| *value != 0);
+                let bound = a.1.checked_mul(cb).zip(b.1.checked_mul(ca));
+                let Some(bound) = bound.and_then(|(a, b)| a.checked_add(b)) else {
+                    return true;
+                };
+                inequalities.push((terms, bound));
+            }
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum Target {
+    Address(usize), // the instruction at this address is about to execute
+    Output(isize),  // the program outputs this value
+    Exit,
+}
+
+#[derive(Clone, Debug)]
+pub struct Path {
+    pub conditions: Vec<Condition>,
+    pub outputs: Vec<Poly>,
+    pub num_inputs: usize, // inputs read so far
+}
+
+impl Path {
+    // whether these inputs take this path, None if they are too few or
+    // overflow
+    pub fn matches(&self, inputs: &[isize]) -> Option<bool> {
+        for condition in &self.conditions {
+            if !condition.eval(inputs)? {
+                return Some(false);
+            }
+        }
+        Some(true)
+    }
+
+    // adds a condition, returning false if it contradicts the others
+    fn assume(&mut self, condition: Condition) -> bool {
+        if self.conditions.contains(&condition) {
+            return true;
+        }
+        if self.conditions.contains(&condition.negated()) {
+            return false;
+        }
+        self.conditions.push(condition);
+        if !feasible(&self.conditions) {
+            self.conditions.pop();
+            return false;
+        }
+        true
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.conditions.len() {
+            0 => write!(f, "always")?,
+            _ => write!(f, "{}", self.conditions.iter().join(" and "))?,
+        }
+        if !self.outputs.is_empty() {
+            write!(f, " => out {}", self.outputs.iter().join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// what became of the paths explored
+#[derive(Clone, Debug, Default)]
+pub struct Exploration {
+    pub reached: Vec<Path>, // paths reaching the target
+    pub missed: usize,      // paths exiting without reaching it
+    pub faulted: usize,
+    pub abandoned: Vec<(Path, String)>, // paths given up on, and why
+}
+
+impl Exploration {
+    // Whether these inputs reach the target. None if they take a path that
+    // was given up on, or if the inputs are too few or overflow.
+    pub fn reaches(&self, inputs: &[isize]) -> Option<bool> {
+        for path in &self.reached {
+            if path.matches(inputs)? {
+                return Some(true);
+            }
+        }
+        for (path, _) in &self.abandoned {
+            if path.matches(inputs)? {
+                return None;
+            }
+        }
+        Some(false)
+    }
+}
+
+#[derive(Clone)]
+struct State {
+    memory: HashMap<usize, Poly>, // words that differ from the image
+    index: usize,
+    relative_base: isize,
+    num_steps: usize,
+    path: Path,
+}
+
+enum Stop {
+    Reached,
+    Exited,
+    Faulted,
+    Abandoned(String),
+}
+
+fn abandon(reason: &str) -> Stop {
+    Stop::Abandoned(reason.to_string())
+}
+
+pub struct SymbolicProgram {
+    intcode: Vec<isize>,
+    inputs: Vec<Option<isize>>, // concrete values for some inputs
+    max_steps: usize,           // per path
+    max_paths: usize,
+}
+
+impl SymbolicProgram {
+    pub fn new(intcode: &[isize]) -> Self {
+        Self {
+            intcode: intcode.to_vec(),
+            inputs: vec![],
+            max_steps: 100_000,
+            max_paths: 10_000,
+        }
+    }
+
+    // gives an input a concrete value instead of a symbol
+    pub fn set_input(&mut self, index: usize, value: isize) {
+        if self.inputs.len() <= index {
+            self.inputs.resize(index + 1, None);
+        }
+        self.inputs[index] = Some(value);
+    }
+
+    pub fn set_limits(&mut self, max_steps: usize, max_paths: usize) {
+        self.max_steps = max_steps;
+        self.max_paths = max_paths;
+    }
+
+    // explores the paths from the start, depth first, until they reach the
+    // target, exit, fault or are abandoned
+    pub fn explore(&self, target: Target) -> Exploration {
+        let mut exploration = Exploration::default();
+        let mut pending = vec![State {
+            memory: HashMap::new(),
+            index: 0,
+            relative_base: 0,
+            num_steps: 0,
+            path: Path {
+                conditions: vec![],
+                outputs: vec![],
+                num_inputs: 0,
+            },
+        }];
+        let mut num_paths = 1;
+        while let Some(mut state) = pending.pop() {
+            let stop = loop {
+                let mut fork = None;
+                let result = self._step(&mut state, target, &mut fork);
+                if let Some(fork) = fork {
+                    if num_paths == self.max_paths {
+                        exploration
+                            .abandoned
+                            .push((fork.path, "too many paths".to_string()));
+                    } else {
+                        num_paths += 1;
+                        pending.push(fork);
+                    }
+                }
+                if let Err(stop) = result {
+                    break stop;
+                }
+            };
+            match stop {
+                Stop::Reached => exploration.reached.push(state.path),
+                Stop::Exited => exploration.missed += 1,
+                Stop::Faulted => exploration.faulted += 1,
+                Stop::Abandoned(reason) => exploration.abandoned.push((state.path, reason)),
+            }
+        }
+        exploration
+    }
+
+    fn _read(&self, state: &State, address: usize) -> Poly {
+        match state.memory.get(&address) {
+            Some(value) => value.clone(),
+            None => Poly::constant(*self.intcode.get(address).unwrap_or(&0)),
+        }
+    }
+
+    fn _concrete(value: &Poly, what: &str) -> Result<isize, Stop> {
+        value
+            .as_constant()
+            .ok_or_else(|| Stop::Abandoned(format!("symbolic {}: {}", what, value)))
+    }
+
+    fn _address(value: isize) -> Result<usize, Stop> {
+        usize::try_from(value).map_err(|_| Stop::Faulted)
+    }
+
+    // Whether poly is in relation to 0 on this path. When that depends on
+    // the inputs, the path assumes it is, and a fork assuming it is not is
+    // left to execute the same instruction again, so this has to be called
+    // before the instruction changes the state.
+    fn _decide(
+        state: &mut State,
+        poly: Poly,
+        relation: Relation,
+        fork: &mut Option<State>,
+    ) -> Result<bool, Stop> {
+        let condition = match Condition::new(poly, relation) {
+            Ok(condition) => condition,
+            Err(Some(holds)) => return Ok(holds),
+            Err(None) => return Err(abandon("overflow")),
+        };
+        // conditions implied by the path are not added to it
+        let mut other = state.clone();
+        if !other.path.assume(condition.negated()) {
+            return Ok(true);
+        }
+        if !state.path.assume(condition) {
+            return Ok(false);
+        }
+        *fork = Some(other);
+        Ok(true)
+    }
+
+    // executes one instruction, or tells why the path stops
+    fn _step(
+        &self,
+        state: &mut State,
+        target: Target,
+        fork: &mut Option<State>,
+    ) -> Result<(), Stop> {
+        if target == Target::Address(state.index) {
+            return Err(Stop::Reached);
+        }
+        if state.num_steps == self.max_steps {
+            return Err(abandon("too many steps"));
+        }
+        let op_code = Self::_concrete(&self._read(state, state.index), "op code")?;
+        let (op, modes) = Program::_parse_op_code(op_code);
+        let Some(OpInfo {
+            num_args,
+            result_arg,
+            ..
+        }) = op_info(op)
+        else {
+            return Err(Stop::Faulted);
+        };
+        let mut args = [0; 3];
+        for (arg_index, arg) in args.iter_mut().enumerate().take(num_args) {
+            let param_address = state.index + arg_index + 1;
+            let param = || Self::_concrete(&self._read(state, param_address), "address");
+            *arg = match modes.get(arg_index).unwrap_or(&0) {
+                0 => Self::_address(param()?)?,
+                1 if result_arg == Some(arg_index) => return Err(Stop::Faulted),
+                1 => param_address,
+                2 => {
+                    let address = param()?.checked_add(state.relative_base);
+                    Self::_address(address.ok_or(Stop::Faulted)?)?
+                }
+                _ => return Err(Stop::Faulted),
+            };
+        }
+        let (a, b) = (self._read(state, args[0]), self._read(state, args[1]));
+        let overflow = || abandon("overflow");
+        let mut next = state.index + 1 + num_args;
+        let result = match op {
+            1 => Some(a.add(&b).ok_or_else(overflow)?),
+            2 => Some(a.mul(&b).ok_or_else(overflow)?),
+            3 => {
+                let index = state.path.num_inputs;
+                state.path.num_inputs += 1;
+                Some(match self.inputs.get(index) {
+                    Some(Some(value)) => Poly::constant(*value),
+                    _ => Poly::input(index),
+                })
+            }
+            4 => {
+                let reached = match target {
+                    Target::Output(value) => {
+                        let difference = a.sub(&Poly::constant(value)).ok_or_else(overflow)?;
+                        Self::_decide(state, difference, Zero, fork)?
+                    }
+                    _ => false,
+                };
+                state.path.outputs.push(a);
+                if reached {
+                    return Err(Stop::Reached);
+                }
+                None
+            }
+            5 | 6 => {
+                let relation = if op == 5 { NonZero } else { Zero };
+                if Self::_decide(state, a, relation, fork)? {
+                    next = Self::_address(Self::_concrete(&b, "jump target")?)?;
+                }
+                None
+            }
+            7 | 8 => {
+                let difference = a.sub(&b).ok_or_else(overflow)?;
+                let relation = if op == 7 { Negative } else { Zero };
+                let holds = Self::_decide(state, difference, relation, fork)?;
+                Some(Poly::constant(holds as isize))
+            }
+            9 => {
+                let offset = Self::_concrete(&a, "relative base offset")?;
+                let relative_base = state.relative_base.checked_add(offset);
+                state.relative_base = relative_base.ok_or(Stop::Faulted)?;
+                None
+            }
+            99 => {
+                return Err(match target {
+                    Target::Exit => Stop::Reached,
+                    _ => Stop::Exited,
+                });
+            }
+            _ => unreachable!(),
+        };
+        if let (Some(result), Some(result_arg)) = (result, result_arg) {
+            state.memory.insert(args[result_arg], result);
+        }
+        state.num_steps += 1;
+        state.index = next;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::asm::assemble;
+    use super::*;
+
+    fn explore(source: &str, target: Target) -> Exploration {
+        SymbolicProgram::new(&assemble(source).unwrap()).explore(target)
+    }
+
+    #[test]
+    fn day19_beam() {
+        let input = include_str!("../../data/day19.txt");
+        let intcode: Vec<isize> = input
+            .trim()
+            .split(',')
+            .map(|n| n.parse().unwrap())
+            .collect();
+        let beam = SymbolicProgram::new(&intcode).explore(Target::Output(1));
+        let mut num_decided = 0;
+        let far = (0..20).map(|i| (600 + 13 * i, 900 + 7 * i));
+        for (x, y) in (0..50)
+            .flat_map(|y| (0..50).map(move |x| (x, y)))
+            .chain(far)
+        {
+            let mut program = Program::new(&intcode);
+            program.send(x);
+            program.send(y);
+            program.run_until_output().unwrap();
+            let in_beam = program.receive() == Some(1);
+            if let Some(reaches) = beam.reaches(&[x, y]) {
+                assert_eq!(reaches, in_beam, "at {},{}", x, y);
+                num_decided += 1;
+            }
+        }
+        assert!(num_decided > 2000);
+    }
+
+    #[test]
+    fn branches_on_inputs() {
+        let source = "
+                  IN -> [a]
+                  IN -> [b]
+                  LT [a], [b] -> [t]
+                  JNZ [t], #less
+                  OUT #0
+                  HLT
+            less: OUT #1
+                  HLT
+            a:    .data 0
+            b:    .data 0
+            t:    .data 0
+        ";
+        let exploration = explore(source, Target::Output(1));
+        assert_eq!(exploration.reached.len(), 1);
+        assert_eq!(exploration.missed, 1);
+        assert_eq!(exploration.reaches(&[1, 2]), Some(true));
+        assert_eq!(exploration.reaches(&[-5, -4]), Some(true));
+        assert_eq!(exploration.reaches(&[2, 1]), Some(false));
+        assert_eq!(exploration.reaches(&[3, 3]), Some(false));
+        assert_eq!(exploration.reaches(&[3]), None);
+        let exploration = explore(source, Target::Address(14));
+        assert_eq!(exploration.reached.len(), 1);
+        assert_eq!(exploration.reaches(&[1, 2]), Some(true));
+        assert_eq!(exploration.reaches(&[2, 1]), Some(false));
+    }
+
+    // x < y, y < z and then either x < z, which has to hold, or z < x, which
+    // cannot, as only eliminating y tells
+    fn ordered(last: &str) -> String {
+        format!(
+            "
+                  IN -> [x]
+                  IN -> [y]
+                  IN -> [z]
+                  LT [x], [y] -> [t]
+                  JZ [t], #end
+                  LT [y], [z] -> [t]
+                  JZ [t], #end
+                  {}
+                  JZ [t], #end
+                  OUT #1
+            end:  HLT
+            x:    .data 0
+            y:    .data 0
+            z:    .data 0
+            t:    .data 0
+            ",
+            last
+        )
+    }
+
+    #[test]
+    fn feasible_and_infeasible_paths() {
+        let exploration = explore(&ordered("LT [x], [z] -> [t]"), Target::Output(1));
+        assert_eq!(exploration.reached.len(), 1);
+        // the path to the output never assumes x < z, it follows from the rest
+        assert_eq!(exploration.reached[0].conditions.len(), 2);
+        assert_eq!(exploration.missed, 2);
+        assert_eq!(exploration.reaches(&[1, 2, 3]), Some(true));
+        assert_eq!(exploration.reaches(&[1, 3, 2]), Some(false));
+        let exploration = explore(&ordered("LT [z], [x] -> [t]"), Target::Output(1));
+        // the path through all three comparisons misses too, without a fork
+        assert!(exploration.reached.is_empty());
+        assert_eq!(exploration.missed, 3);
+        assert_eq!(exploration.reaches(&[1, 2, 3]), Some(false));
+        // 2x == 1 has no integer solution
+        let source = "
+               IN -> [x]
+               MUL [x], #2 -> [t]
+               EQ [t], #1 -> [t]
+               JZ [t], #end
+               OUT #1
+            end: HLT
+            x: .data 0
+            t: .data 0
+        ";
+        let exploration = explore(source, Target::Output(1));
+        assert!(exploration.reached.is_empty());
+        assert_eq!(exploration.missed, 1);
+    }
+
+    #[test]
+    fn polynomial_outputs() {
+        // (x + 1)(x + 1) - x * x, and whether x * x == 4
+        let source = "
+               IN -> [x]
+               ADD [x], #1 -> [t]
+               MUL [t], [t] -> [t]
+               MUL [x], [x] -> [u]
+               MUL [u], #-1 -> [v]
+               ADD [t], [v] -> [t]
+               OUT [t]
+               EQ [u], #4 -> [t]
+               OUT [t]
+               HLT
+            x: .data 0
+            t: .data 0
+            u: .data 0
+            v: .data 0
+        ";
+        let exploration = explore(source, Target::Exit);
+        assert_eq!(exploration.reached.len(), 2);
+        let expected = Poly::input(0).scale(2).unwrap();
+        let expected = expected.add(&Poly::constant(1)).unwrap();
+        let square = Poly::input(0).mul(&Poly::input(0)).unwrap();
+        let square_is_4 = Condition::new(square.sub(&Poly::constant(4)).unwrap(), Zero).unwrap();
+        for path in &exploration.reached {
+            assert_eq!(path.outputs[0], expected);
+            let assumed = path.conditions == [square_is_4.clone()];
+            assert!(assumed || path.conditions == [square_is_4.negated()]);
+            assert_eq!(path.outputs[1], Poly::constant(assumed as isize));
+            assert_eq!(path.matches(&[2]), Some(assumed));
+            assert_eq!(path.matches(&[-2]), Some(assumed));
+            assert_eq!(path.matches(&[3]), Some(!assumed));
+        }
+        let product = Poly::input(0).mul(&Poly::input(1)).unwrap();
+        let negative = Condition::new(product, Negative).unwrap();
+        assert_eq!(negative.eval(&[3, -2]), Some(true));
+        assert_eq!(negative.negated().eval(&[3, -2]), Some(false));
+        let overflow = Poly::input(0).scale(isize::MAX).unwrap();
+        assert_eq!(overflow.eval(&[2]), None);
+    }
+}
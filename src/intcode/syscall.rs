@@ -0,0 +1,176 @@
+use super::opcodes::{OpcodeRegistry, Operands};
+use super::{IntcodeError, OpInfo};
+use hashbrown::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Host functions that Intcode programs call with the SYS instruction, added
+// to a program by ProgramBuilder::syscalls():
+//
+//     SYS number, arg -> result
+//
+// calls the function registered under number with arg and stores what it
+// returns. Functions needing more than one value take the address of an
+// argument block. Strings are NUL terminated, one character per word, and
+// file contents are one byte per word. Failures the program can handle are
+// reported as negative results; an unknown function number or an invalid
+// string is a fault instead, raising a Trap with the codes below.
+
+pub const SYSCALL_OP: isize = 80;
+pub const SYSCALL_INFO: OpInfo = OpInfo {
+    mnemonic: "SYS",
+    num_args: 3,
+    result_arg: Some(2),
+};
+
+pub const UNKNOWN_SYSCALL: isize = 1;
+pub const INVALID_STRING: isize = 2;
+
+// the functions of Syscalls::standard()
+pub const SYS_PRINT: isize = 1; // (string) -> number of characters printed
+pub const SYS_TIME: isize = 2; // (_) -> milliseconds since the Unix epoch
+pub const SYS_RANDOM: isize = 3; // (bound) -> random number in 0..bound, or >= 0
+pub const SYS_READ_FILE: isize = 4; // (&[path, buffer, capacity]) -> bytes read, or -1
+
+const MAX_STRING_LEN: usize = 1 << 20;
+
+pub type HostFunction =
+    Arc<dyn Fn(&mut Operands, isize) -> Result<isize, IntcodeError> + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub struct Syscalls {
+    functions: HashMap<isize, (&'static str, HostFunction)>,
+}
+
+impl Syscalls {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // printing to stdout, the time and random numbers, but no file access
+    // until allow_files()
+    pub fn standard() -> Self {
+        let mut syscalls = Syscalls::new();
+        syscalls.register(SYS_PRINT, "print", |ops, address| {
+            let text = read_string(ops, address)?;
+            let mut stdout = io::stdout().lock();
+            let printed = stdout
+                .write_all(text.as_bytes())
+                .and_then(|_| stdout.flush());
+            Ok(match printed {
+                Ok(()) => text.chars().count() as isize,
+                Err(_) => -1,
+            })
+        });
+        syscalls.register(SYS_TIME, "time", |_, _| {
+            let elapsed = SystemTime::now().duration_since(UNIX_EPOCH);
+            Ok(elapsed.map_or(-1, |elapsed| elapsed.as_millis() as isize))
+        });
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        let state = AtomicU64::new(seed | 1);
+        syscalls.register(SYS_RANDOM, "random", move |_, bound| {
+            // xorshift64*
+            let mut x = state.load(Ordering::Relaxed);
+            x ^= x >> 12;
+            x ^= x << 25;
+            x ^= x >> 27;
+            state.store(x, Ordering::Relaxed);
+            let value = (x.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 1) as isize;
+            Ok(if bound > 0 { value % bound } else { value })
+        });
+        syscalls
+    }
+
+    // lets SYS_READ_FILE read the files under root, given their relative path
+    pub fn allow_files(&mut self, root: impl Into<PathBuf>) {
+        let root = root.into();
+        self.register(SYS_READ_FILE, "read_file", move |ops, block| {
+            let Ok(block) = usize::try_from(block) else {
+                return Ok(-1);
+            };
+            let path_address = ops.read(block);
+            let (buffer, capacity) = (ops.read(block + 1), ops.read(block + 2));
+            let path = read_string(ops, path_address)?;
+            let path = Path::new(&path);
+            let (Ok(buffer), Ok(capacity)) = (usize::try_from(buffer), usize::try_from(capacity))
+            else {
+                return Ok(-1);
+            };
+            if !path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+            {
+                return Ok(-1);
+            }
+            // symbolic links must not lead out of root either
+            let (Ok(root), Ok(path)) = (root.canonicalize(), root.join(path).canonicalize()) else {
+                return Ok(-1);
+            };
+            if !path.starts_with(&root) {
+                return Ok(-1);
+            }
+            let mut contents = vec![];
+            let read = File::open(path)
+                .and_then(|file| file.take(capacity as u64).read_to_end(&mut contents));
+            if read.is_err() {
+                return Ok(-1);
+            }
+            for (offset, byte) in contents.iter().enumerate() {
+                ops.write(buffer + offset, *byte as isize);
+            }
+            Ok(contents.len() as isize)
+        });
+    }
+
+    // replaces any function registered under the same number
+    pub fn register<F>(&mut self, number: isize, name: &'static str, function: F)
+    where
+        F: Fn(&mut Operands, isize) -> Result<isize, IntcodeError> + Send + Sync + 'static,
+    {
+        self.functions.insert(number, (name, Arc::new(function)));
+    }
+
+    // registered functions, by number
+    pub fn functions(&self) -> impl Iterator<Item = (isize, &'static str)> + '_ {
+        self.functions
+            .iter()
+            .map(|(number, (name, _))| (*number, *name))
+    }
+
+    // the SYS instruction, calling these functions
+    pub fn opcodes(&self) -> OpcodeRegistry {
+        let functions = self.functions.clone();
+        let mut opcodes = OpcodeRegistry::new();
+        opcodes.register(SYSCALL_OP, SYSCALL_INFO, move |ops| {
+            let (number, arg) = (ops.load(0), ops.load(1));
+            let Some((_, function)) = functions.get(&number) else {
+                return Err(ops.trap(UNKNOWN_SYSCALL));
+            };
+            let result = function(ops, arg)?;
+            ops.store(result);
+            Ok(())
+        });
+        opcodes
+    }
+}
+
+// the NUL terminated string at address, for host functions
+pub fn read_string(ops: &mut Operands, address: isize) -> Result<String, IntcodeError> {
+    let address = usize::try_from(address).map_err(|_| ops.trap(INVALID_STRING))?;
+    let mut text = String::new();
+    for offset in 0..MAX_STRING_LEN {
+        let value = ops.read(address + offset);
+        if value == 0 {
+            return Ok(text);
+        }
+        let c = u32::try_from(value).ok().and_then(char::from_u32);
+        text.push(c.ok_or_else(|| ops.trap(INVALID_STRING))?);
+    }
+    Err(ops.trap(INVALID_STRING))
+}
@@ -0,0 +1,220 @@
+use super::{Program, ProgramState::*};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+// Async programs for single-threaded executors: an AsyncProgram awaits its
+// input on a channel Receiver and sends its outputs to a channel Sender, so
+// that many of them (and any other futures) can share one thread. No
+// particular executor is needed, but LocalExecutor is enough to run them.
+
+struct Channel {
+    queue: VecDeque<isize>,
+    num_senders: usize,
+    receiver_alive: bool,
+    waker: Option<Waker>,
+}
+
+impl Channel {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+// unbounded, the Receiver sees the end of the stream once every Sender is gone
+pub fn channel() -> (Sender, Receiver) {
+    let channel = Rc::new(RefCell::new(Channel {
+        queue: VecDeque::new(),
+        num_senders: 1,
+        receiver_alive: true,
+        waker: None,
+    }));
+    (Sender(channel.clone()), Receiver(channel))
+}
+
+pub struct Sender(Rc<RefCell<Channel>>);
+
+impl Sender {
+    // returns false if the receiver is gone
+    pub fn send(&self, value: isize) -> bool {
+        let mut channel = self.0.borrow_mut();
+        if !channel.receiver_alive {
+            return false;
+        }
+        channel.queue.push_back(value);
+        channel.wake();
+        true
+    }
+}
+
+impl Clone for Sender {
+    fn clone(&self) -> Self {
+        self.0.borrow_mut().num_senders += 1;
+        Sender(self.0.clone())
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        let mut channel = self.0.borrow_mut();
+        channel.num_senders -= 1;
+        if channel.num_senders == 0 {
+            channel.wake();
+        }
+    }
+}
+
+pub struct Receiver(Rc<RefCell<Channel>>);
+
+impl Receiver {
+    // the next value, None at the end of the stream
+    pub async fn recv(&mut self) -> Option<isize> {
+        std::future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    pub fn poll_recv(&mut self, cx: &mut Context) -> Poll<Option<isize>> {
+        let mut channel = self.0.borrow_mut();
+        match channel.queue.pop_front() {
+            Some(value) => Poll::Ready(Some(value)),
+            None if channel.num_senders == 0 => Poll::Ready(None),
+            None => {
+                channel.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Option<isize> {
+        self.0.borrow_mut().queue.pop_front()
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        self.0.borrow_mut().receiver_alive = false;
+    }
+}
+
+// lets other tasks run, once
+pub async fn yield_now() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+    .await
+}
+
+// number of instructions an AsyncProgram executes before it lets others run
+const STEPS_PER_YIELD: usize = 4096;
+
+pub struct AsyncProgram {
+    program: Program,
+    input: Receiver,
+    output: Sender,
+}
+
+impl AsyncProgram {
+    pub fn new(program: Program, input: Receiver, output: Sender) -> Self {
+        Self {
+            program,
+            input,
+            output,
+        }
+    }
+
+    // Runs until the program exits, faults, or waits for input after the end
+    // of the input stream, and hands it back; check its state() and fault().
+    // Outputs nobody receives any more are dropped.
+    pub async fn run(mut self) -> Program {
+        let mut num_steps = 0;
+        loop {
+            if self.program.step().is_err() {
+                break;
+            }
+            while let Some(value) = self.program.receive() {
+                self.output.send(value);
+            }
+            match self.program.state() {
+                Running => {
+                    num_steps += 1;
+                    if num_steps % STEPS_PER_YIELD == 0 {
+                        yield_now().await;
+                    }
+                }
+                WaitingForInput => match self.input.recv().await {
+                    Some(value) => self.program.send(value),
+                    None => break,
+                },
+                Exited | Faulted | BudgetExhausted => break,
+            }
+        }
+        self.program
+    }
+}
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+struct TaskWaker {
+    id: usize,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.ready.lock().unwrap().push_back(self.id);
+    }
+}
+
+// polls its tasks in turn on the current thread
+#[derive(Default)]
+pub struct LocalExecutor {
+    tasks: Vec<Option<Task>>,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl LocalExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self, future: impl Future<Output = ()> + 'static) {
+        self.ready.lock().unwrap().push_back(self.tasks.len());
+        self.tasks.push(Some(Box::pin(future)));
+    }
+
+    // Runs until every task has completed and returns true, or returns false
+    // as soon as the remaining tasks are all waiting for something that only
+    // code outside the executor could provide.
+    pub fn run(&mut self) -> bool {
+        loop {
+            let Some(id) = self.ready.lock().unwrap().pop_front() else {
+                return self.tasks.iter().all(|task| task.is_none());
+            };
+            let Some(task) = &mut self.tasks[id] else {
+                continue; // woken after it completed
+            };
+            let waker = Waker::from(Arc::new(TaskWaker {
+                id,
+                ready: self.ready.clone(),
+            }));
+            if task
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                self.tasks[id] = None;
+            }
+        }
+    }
+}
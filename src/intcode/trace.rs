@@ -0,0 +1,356 @@
+use super::ProgramState::{self, *};
+use super::{op_info, varint};
+use std::io::{self, Read, Write};
+
+// Hooks called by Program::step() while executing. before_instruction() gets
+// the resolved argument addresses and the values they currently hold; it is
+// not called for an input instruction that has to wait for input.
+pub trait Tracer<W = isize> {
+    fn before_instruction(
+        &mut self,
+        _index: usize,
+        _op_code: isize,
+        _args: &[usize],
+        _values: &[W],
+    ) {
+    }
+    fn read(&mut self, _address: usize, _value: W) {}
+    fn write(&mut self, _address: usize, _value: W) {}
+    fn input(&mut self, _value: W) {}
+    fn output(&mut self, _value: W) {}
+    fn halt(&mut self, _index: usize, _state: ProgramState) {}
+    // flushes what the tracer writes to, returning the first error it ran into
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Prints one line of pseudo-code per instruction. Once writing fails, it
+// stops writing and keeps the error for finish().
+pub struct TextTracer<W: Write> {
+    writer: W,
+    pending_input: Option<(usize, usize)>,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> TextTracer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            pending_input: None,
+            error: None,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn _write(&mut self, write: impl FnOnce(&mut W) -> io::Result<()>) {
+        if self.error.is_none() {
+            self.error = write(&mut self.writer).err();
+        }
+    }
+}
+
+impl<W: Write> Tracer for TextTracer<W> {
+    fn before_instruction(
+        &mut self,
+        index: usize,
+        op_code: isize,
+        args: &[usize],
+        values: &[isize],
+    ) {
+        let line = match op_code % 100 {
+            1 => format!("&{} = {} + {}", args[2], values[0], values[1]),
+            2 => format!("&{} = {} * {}", args[2], values[0], values[1]),
+            3 => {
+                // printed once the input value is known
+                self.pending_input = Some((index, args[0]));
+                return;
+            }
+            4 => format!("{} (out)", values[0]),
+            5 => format!("if {} != 0 goto &{}", values[0], values[1]),
+            6 => format!("if {} == 0 goto &{}", values[0], values[1]),
+            7 => format!("&{} = {} < {} ? 1 : 0", args[2], values[0], values[1]),
+            8 => format!("&{} = {} == {} ? 1 : 0", args[2], values[0], values[1]),
+            9 => format!("rbase += {}", values[0]),
+            99 => return, // printed by halt()
+            _ => format!("op code {}", op_code),
+        };
+        self._write(|writer| writeln!(writer, "{:>6}: {}", index, line));
+    }
+
+    fn input(&mut self, value: isize) {
+        if let Some((index, address)) = self.pending_input.take() {
+            self._write(|writer| writeln!(writer, "{:>6}: &{} = {} (in)", index, address, value));
+        }
+    }
+
+    fn halt(&mut self, index: usize, state: ProgramState) {
+        let line = if state == Exited { "EXIT" } else { "FAULT" };
+        self._write(|writer| {
+            writeln!(writer, "{:>6}: {}", index, line)?;
+            writer.flush()
+        });
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self._write(|writer| writer.flush());
+        self.error.take().map_or(Ok(()), Err)
+    }
+}
+
+// Binary traces start with MAGIC, followed by one record per event: a tag
+// byte and the event's fields as varints (instruction records
+// also store the number of arguments before the addresses and values).
+const MAGIC: &[u8] = b"ICTRACE\x01";
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum TraceEvent {
+    Instruction {
+        index: usize,
+        op_code: isize,
+        args: Vec<usize>,
+        values: Vec<isize>,
+    },
+    Read(usize, isize),
+    Write(usize, isize),
+    Input(isize),
+    Output(isize),
+    Halt(usize, ProgramState),
+}
+
+impl TraceEvent {
+    // feeds the event to a tracer, as if it had just happened
+    pub fn replay(&self, tracer: &mut dyn Tracer) {
+        match self {
+            TraceEvent::Instruction {
+                index,
+                op_code,
+                args,
+                values,
+            } => tracer.before_instruction(*index, *op_code, args, values),
+            TraceEvent::Read(address, value) => tracer.read(*address, *value),
+            TraceEvent::Write(address, value) => tracer.write(*address, *value),
+            TraceEvent::Input(value) => tracer.input(*value),
+            TraceEvent::Output(value) => tracer.output(*value),
+            TraceEvent::Halt(index, state) => tracer.halt(*index, *state),
+        }
+    }
+}
+
+// like TextTracer, stops writing once that fails
+pub struct BinaryTracer<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> BinaryTracer<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        Ok(Self {
+            writer,
+            error: None,
+        })
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn record(&mut self, tag: u8, fields: &[isize]) {
+        let mut buffer = vec![tag];
+        for field in fields {
+            varint::encode(*field, &mut buffer);
+        }
+        if self.error.is_none() {
+            self.error = self.writer.write_all(&buffer).err();
+        }
+    }
+}
+
+impl<W: Write> Tracer for BinaryTracer<W> {
+    fn before_instruction(
+        &mut self,
+        index: usize,
+        op_code: isize,
+        args: &[usize],
+        values: &[isize],
+    ) {
+        let mut fields = vec![index as isize, op_code, args.len() as isize];
+        fields.extend(args.iter().map(|arg| *arg as isize));
+        fields.extend(values);
+        self.record(0, &fields);
+    }
+
+    fn read(&mut self, address: usize, value: isize) {
+        self.record(1, &[address as isize, value]);
+    }
+
+    fn write(&mut self, address: usize, value: isize) {
+        self.record(2, &[address as isize, value]);
+    }
+
+    fn input(&mut self, value: isize) {
+        self.record(3, &[value]);
+    }
+
+    fn output(&mut self, value: isize) {
+        self.record(4, &[value]);
+    }
+
+    fn halt(&mut self, index: usize, state: ProgramState) {
+        self.record(5, &[index as isize, (state == Faulted) as isize]);
+        if self.error.is_none() {
+            self.error = self.writer.flush().err();
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.error.is_none() {
+            self.error = self.writer.flush().err();
+        }
+        self.error.take().map_or(Ok(()), Err)
+    }
+}
+
+pub struct TraceReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> TraceReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an Intcode trace",
+            ));
+        }
+        Ok(Self { reader })
+    }
+
+    fn read_field(&mut self) -> io::Result<isize> {
+        varint::decode(&mut self.reader)
+    }
+
+    fn read_event(&mut self) -> io::Result<Option<TraceEvent>> {
+        let Some(tag) = varint::read_byte(&mut self.reader)? else {
+            return Ok(None);
+        };
+        let event = match tag {
+            0 => {
+                let index = self.read_field()? as usize;
+                let op_code = self.read_field()?;
+                let num_args = self.read_field()?;
+                // tracers index the arguments by op, so they must match it
+                let expected = op_info(op_code % 100).map(|info| info.num_args as isize);
+                if !(0..=3).contains(&num_args) || expected.is_some_and(|n| n != num_args) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{} arguments for op code {}", num_args, op_code),
+                    ));
+                }
+                let num_args = num_args as usize;
+                let args = (0..num_args)
+                    .map(|_| self.read_field().map(|arg| arg as usize))
+                    .collect::<io::Result<_>>()?;
+                let values = (0..num_args)
+                    .map(|_| self.read_field())
+                    .collect::<io::Result<_>>()?;
+                TraceEvent::Instruction {
+                    index,
+                    op_code,
+                    args,
+                    values,
+                }
+            }
+            1 => TraceEvent::Read(self.read_field()? as usize, self.read_field()?),
+            2 => TraceEvent::Write(self.read_field()? as usize, self.read_field()?),
+            3 => TraceEvent::Input(self.read_field()?),
+            4 => TraceEvent::Output(self.read_field()?),
+            5 => {
+                let index = self.read_field()? as usize;
+                let state = if self.read_field()? != 0 {
+                    Faulted
+                } else {
+                    Exited
+                };
+                TraceEvent::Halt(index, state)
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid trace record tag {}", tag),
+                ))
+            }
+        };
+        Ok(Some(event))
+    }
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = io::Result<TraceEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_event().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(bytes: &[u8]) -> io::Result<Vec<TraceEvent>> {
+        TraceReader::new(bytes)?.collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let events = vec![
+            TraceEvent::Instruction {
+                index: 0,
+                op_code: 1002,
+                args: vec![9, 3, 9],
+                values: vec![14, 3, 14],
+            },
+            TraceEvent::Read(9, 14),
+            TraceEvent::Write(9, 42),
+            TraceEvent::Instruction {
+                index: 4,
+                op_code: 4,
+                args: vec![9],
+                values: vec![42],
+            },
+            TraceEvent::Output(42),
+            TraceEvent::Halt(6, Exited),
+        ];
+        let mut tracer = BinaryTracer::new(vec![]).unwrap();
+        for event in &events {
+            event.replay(&mut tracer);
+        }
+        tracer.finish().unwrap();
+        assert_eq!(decode(&tracer.into_inner()).unwrap(), events);
+    }
+
+    #[test]
+    fn arguments_not_matching_the_op() {
+        // an ADD record with no arguments, as a corrupt file might hold
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(0);
+        for field in [0, 1, 0] {
+            varint::encode(field, &mut bytes);
+        }
+        let error = decode(&bytes).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(0);
+        for field in [0, 42, -1] {
+            varint::encode(field, &mut bytes);
+        }
+        let error = decode(&bytes).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,39 @@
+use std::io::{self, Read};
+
+// zigzag LEB128 encoding, which keeps small (including negative) numbers small
+
+pub(crate) fn encode(value: isize, buffer: &mut Vec<u8>) {
+    let mut value = ((value << 1) ^ (value >> (isize::BITS - 1))) as usize;
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+pub(crate) fn read_byte<R: Read>(reader: &mut R) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    match reader.read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+pub(crate) fn decode<R: Read>(reader: &mut R) -> io::Result<isize> {
+    let mut value: usize = 0;
+    let mut shift = 0;
+    loop {
+        let byte = read_byte(reader)?.ok_or(io::ErrorKind::UnexpectedEof)?;
+        if shift >= usize::BITS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "varint too long",
+            ));
+        }
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((value >> 1) as isize ^ -((value & 1) as isize));
+        }
+        shift += 7;
+    }
+}
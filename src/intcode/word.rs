@@ -0,0 +1,372 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+// What happens when ADD or MUL overflows the word type. Programs trap by
+// default, faulting with IntcodeError::Overflow.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub enum OverflowPolicy {
+    #[default]
+    Trap,
+    Wrap,     // two's complement
+    Saturate, // to the smallest or largest word
+}
+
+use OverflowPolicy::*;
+
+// The type of the values a Program computes with: any of the primitive signed
+// integers, or BigInt which never overflows.
+pub trait Word: Clone + Ord + fmt::Debug + fmt::Display + FromStr + Send + 'static {
+    fn zero() -> Self;
+    fn from_bool(value: bool) -> Self;
+    fn is_zero(&self) -> bool;
+    fn to_isize(&self) -> Option<isize>;
+    // None if it overflows and the policy is to trap
+    fn add(&self, other: &Self, overflow: OverflowPolicy) -> Option<Self>;
+    fn mul(&self, other: &Self, overflow: OverflowPolicy) -> Option<Self>;
+}
+
+macro_rules! primitive_word {
+    ($($word:ty),*) => {$(
+        impl Word for $word {
+            fn zero() -> Self {
+                0
+            }
+
+            fn from_bool(value: bool) -> Self {
+                value as $word
+            }
+
+            fn is_zero(&self) -> bool {
+                *self == 0
+            }
+
+            fn to_isize(&self) -> Option<isize> {
+                isize::try_from(*self).ok()
+            }
+
+            fn add(&self, other: &Self, overflow: OverflowPolicy) -> Option<Self> {
+                match overflow {
+                    Trap => self.checked_add(*other),
+                    Wrap => Some(self.wrapping_add(*other)),
+                    Saturate => Some(self.saturating_add(*other)),
+                }
+            }
+
+            fn mul(&self, other: &Self, overflow: OverflowPolicy) -> Option<Self> {
+                match overflow {
+                    Trap => self.checked_mul(*other),
+                    Wrap => Some(self.wrapping_mul(*other)),
+                    Saturate => Some(self.saturating_mul(*other)),
+                }
+            }
+        }
+    )*};
+}
+
+primitive_word!(i32, i64, i128, isize);
+
+// Arbitrary precision integer, with just what Intcode needs.
+#[derive(Eq, PartialEq, Hash, Clone, Debug, Default)]
+pub struct BigInt {
+    negative: bool,      // never for zero
+    magnitude: Vec<u32>, // least significant first, without leading zeros
+}
+
+impl BigInt {
+    fn _new(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        BigInt {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    fn _compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+        a.len()
+            .cmp(&b.len())
+            .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+    }
+
+    fn _add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+        let mut carry = 0;
+        for i in 0..a.len().max(b.len()) {
+            let digit = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+            sum.push(digit as u32);
+            carry = digit >> 32;
+        }
+        sum.push(carry as u32);
+        sum
+    }
+
+    // a - b, where a >= b
+    fn _sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut difference = Vec::with_capacity(a.len());
+        let mut borrow = 0;
+        for (i, digit) in a.iter().enumerate() {
+            let digit = *digit as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+            difference.push(digit.rem_euclid(1 << 32) as u32);
+            borrow = (digit < 0) as i64;
+        }
+        difference
+    }
+
+    // multiplies by factor and adds addend, in place
+    fn _mul_add_small(&mut self, factor: u32, addend: u32) {
+        let mut carry = addend as u64;
+        for digit in &mut self.magnitude {
+            let value = *digit as u64 * factor as u64 + carry;
+            *digit = value as u32;
+            carry = value >> 32;
+        }
+        if carry > 0 {
+            self.magnitude.push(carry as u32);
+        }
+    }
+
+    // divides the magnitude by divisor in place, returning the remainder
+    fn _div_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for digit in self.magnitude.iter_mut().rev() {
+            let value = (remainder << 32) | *digit as u64;
+            *digit = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+        *self = BigInt::_new(self.negative, std::mem::take(&mut self.magnitude));
+        remainder as u32
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(value: i128) -> Self {
+        let mut magnitude = value.unsigned_abs();
+        let mut digits = vec![];
+        while magnitude > 0 {
+            digits.push(magnitude as u32);
+            magnitude >>= 32;
+        }
+        BigInt::_new(value < 0, digits)
+    }
+}
+
+impl From<isize> for BigInt {
+    fn from(value: isize) -> Self {
+        BigInt::from(value as i128)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => BigInt::_compare_magnitudes(&self.magnitude, &other.magnitude),
+            (true, true) => BigInt::_compare_magnitudes(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const CHUNK: u32 = 1_000_000_000; // 9 decimal digits
+        let mut rest = self.clone();
+        let mut chunks = vec![];
+        loop {
+            chunks.push(rest._div_small(CHUNK));
+            if rest.magnitude.is_empty() {
+                break;
+            }
+        }
+        let sign = if self.negative { "-" } else { "" };
+        write!(f, "{}{}", sign, chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct ParseBigIntError;
+
+impl fmt::Display for ParseBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid integer")
+    }
+}
+
+impl std::error::Error for ParseBigIntError {}
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if digits.is_empty() {
+            return Err(ParseBigIntError);
+        }
+        let mut value = BigInt::default();
+        for c in digits.chars() {
+            let digit = c.to_digit(10).ok_or(ParseBigIntError)?;
+            value._mul_add_small(10, digit);
+        }
+        Ok(BigInt::_new(negative, value.magnitude))
+    }
+}
+
+impl Word for BigInt {
+    fn zero() -> Self {
+        BigInt::default()
+    }
+
+    fn from_bool(value: bool) -> Self {
+        BigInt::from(value as i128)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    fn to_isize(&self) -> Option<isize> {
+        if self.magnitude.len() > 128 / 32 {
+            return None;
+        }
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0u128, |value, digit| (value << 32) | *digit as u128);
+        let value = i128::try_from(magnitude).ok()?;
+        isize::try_from(if self.negative { -value } else { value }).ok()
+    }
+
+    fn add(&self, other: &Self, _: OverflowPolicy) -> Option<Self> {
+        let (a, b) = (&self.magnitude, &other.magnitude);
+        Some(if self.negative == other.negative {
+            BigInt::_new(self.negative, BigInt::_add_magnitudes(a, b))
+        } else if BigInt::_compare_magnitudes(a, b) == Ordering::Less {
+            BigInt::_new(other.negative, BigInt::_sub_magnitudes(b, a))
+        } else {
+            BigInt::_new(self.negative, BigInt::_sub_magnitudes(a, b))
+        })
+    }
+
+    fn mul(&self, other: &Self, _: OverflowPolicy) -> Option<Self> {
+        let (a, b) = (&self.magnitude, &other.magnitude);
+        let mut product = vec![0u32; a.len() + b.len()];
+        for (i, a_digit) in a.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, b_digit) in b.iter().enumerate() {
+                let value = *a_digit as u64 * *b_digit as u64 + product[i + j] as u64 + carry;
+                product[i + j] = value as u32;
+                carry = value >> 32;
+            }
+            product[i + b.len()] = carry as u32;
+        }
+        Some(BigInt::_new(self.negative != other.negative, product))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{asm::assemble, IntcodeError, Program, ProgramState::*};
+    use super::*;
+
+    // signs, 32-bit limb boundaries and the 64-bit extremes, whose sums all
+    // fit in an i128, as do most of their products
+    fn values() -> Vec<i128> {
+        let mut values = vec![0, 1, 2, 12345, 123_456_789_012_345_678];
+        for bits in [31, 32, 33, 63, 64] {
+            values.extend([(1 << bits) - 1, 1 << bits, (1 << bits) + 1]);
+        }
+        values.extend([i64::MAX as i128, u64::MAX as i128, 0xffff_0000_ffff_0000]);
+        let negated: Vec<i128> = values.iter().map(|value| -value).collect();
+        values.extend(negated);
+        values.push(i64::MIN as i128);
+        values
+    }
+
+    #[test]
+    fn arithmetic() {
+        for a in values() {
+            for b in values() {
+                let (x, y) = (BigInt::from(a), BigInt::from(b));
+                let sum = x.add(&y, OverflowPolicy::Trap).unwrap();
+                assert_eq!(sum, BigInt::from(a + b), "{} + {}", a, b);
+                if let Some(expected) = a.checked_mul(b) {
+                    let product = x.mul(&y, OverflowPolicy::Trap).unwrap();
+                    assert_eq!(product, BigInt::from(expected), "{} * {}", a, b);
+                }
+                assert_eq!(x.cmp(&y), a.cmp(&b), "{} <=> {}", a, b);
+            }
+        }
+        // carries across many limbs, where i128 overflows
+        let max = BigInt::from(i128::MAX);
+        let big = max.mul(&max, OverflowPolicy::Trap).unwrap();
+        let sum = big.add(&BigInt::from(1i128), OverflowPolicy::Trap).unwrap();
+        assert_eq!(
+            sum.to_string(),
+            "28948022309329048855892746252171976962977213799489202546401021394546514198530"
+        );
+        let negative = BigInt::from(-1i128)
+            .mul(&big, OverflowPolicy::Trap)
+            .unwrap();
+        assert!(negative < BigInt::from(i128::MIN));
+        assert!(negative.add(&big, OverflowPolicy::Trap).unwrap().is_zero());
+    }
+
+    #[test]
+    fn conversions() {
+        for value in values() {
+            let big = BigInt::from(value);
+            assert_eq!(big.to_string(), value.to_string());
+            assert_eq!(big.to_string().parse::<BigInt>(), Ok(big.clone()));
+            assert_eq!(big.to_isize(), isize::try_from(value).ok(), "{}", value);
+        }
+        assert_eq!(BigInt::from(i128::MIN).to_isize(), None);
+        assert_eq!("+42".parse::<BigInt>(), Ok(BigInt::from(42i128)));
+        assert_eq!("-0".parse::<BigInt>(), Ok(BigInt::zero()));
+        assert_eq!("007".parse::<BigInt>(), Ok(BigInt::from(7i128)));
+        for invalid in ["", "-", "+", "1.5", "--1", " 1", "1e9"] {
+            assert_eq!(invalid.parse::<BigInt>(), Err(ParseBigIntError));
+        }
+    }
+
+    #[test]
+    fn programs_past_64_bits() {
+        // outputs 3 to the power of its input
+        let source = "
+                  IN -> [n]
+            loop: MUL [x], #3 -> [x]
+                  ADD [n], #-1 -> [n]
+                  JNZ [n], #loop
+                  OUT [x]
+                  HLT
+            n:    .data 0
+            x:    .data 1
+        ";
+        let intcode = assemble(source).unwrap();
+        let big_intcode: Vec<BigInt> = intcode.iter().map(|word| BigInt::from(*word)).collect();
+        let mut program = Program::new(&big_intcode);
+        program.send(BigInt::from(100isize));
+        assert_eq!(program.run(), Ok(Exited));
+        let expected = "515377520732011331036461129765621272702107522001";
+        assert_eq!(program.receive(), expected.parse().ok());
+        let words: Vec<i64> = intcode.iter().map(|word| *word as i64).collect();
+        let mut program = Program::new(&words);
+        program.send(100);
+        assert!(matches!(program.run(), Err(IntcodeError::Overflow { .. })));
+    }
+}